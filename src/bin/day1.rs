@@ -1,82 +1,472 @@
-use aoc::Part;
+use aoc::{render_table, Part};
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use clap::{arg, ArgAction};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+};
+
+// "word value" per line, blank lines and # comments allowed
+const DIGITS: &str = "1 1\n2 2\n3 3\n4 4\n5 5\n6 6\n7 7\n8 8\n9 9";
+const ENGLISH: &str = "one 1
+two 2
+three 3
+four 4
+five 5
+six 6
+seven 7
+eight 8
+nine 9";
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--words <path> "a table of \"word value\" lines to use instead of the english words"),
+        arg!(--"ignore-case" "match words regardless of ascii case").action(ArgAction::SetTrue),
+        arg!(--report "list what was found on each line, and a summary").action(ArgAction::SetTrue),
+        arg!(--missing <policy> "warn (default) or error on a line without any digits"),
+    ])?;
     let mut reader = aoc::open_into_buffered_reader(&problem.path)?;
 
-    let mut allow_list = vec!["1", "2", "3", "4", "5", "6", "7", "8", "9"];
-    if problem.part == Part::P2 {
-        allow_list.append(&mut vec![
-            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-        ]);
+    let mut entries = parse_table(DIGITS)?;
+    match matches.get_one::<String>("words") {
+        Some(path) => {
+            let table = fs::read_to_string(path)?;
+            entries.extend(parse_table(&table).with_context(|| format!("word table {path}"))?);
+        }
+        None if problem.part == Part::P2 => entries.extend(parse_table(ENGLISH)?),
+        None => (),
     }
+    let vocabulary = Vocabulary::new(entries, matches.get_flag("ignore-case"))?;
+    let strict = match matches.get_one::<String>("missing").map(|s| &s[..]) {
+        None | Some("warn") => false,
+        Some("error") => true,
+        Some(policy) => return Err(anyhow!("unknown missing policy {policy}")),
+    };
 
-    let mut calibration = 0;
+    let mut lines = vec![];
     while let Some(line) = aoc::get_line(&mut reader) {
-        let (left, right) = find_num_pairs(&line, &allow_list);
-        calibration += left * 10 + right;
+        lines.push(line);
+    }
+    let readings = read_lines(&lines, &vocabulary);
+    for reading in readings.iter().filter(|reading| reading.tokens.is_none()) {
+        if strict {
+            return Err(anyhow!("line {} has no digits", reading.line));
+        }
+        eprintln!(
+            "warning: line {} has no digits, counting it as 0",
+            reading.line
+        );
     }
+
+    if matches.get_flag("report") {
+        print!("{}", report(&readings));
+    }
+    let calibration: u32 = readings.iter().map(Reading::value).sum();
     println!("calibration value: {calibration}");
 
     Ok(())
 }
 
-fn find_num_pairs(line: &str, allow_list: &[&str]) -> (u32, u32) {
-    // use find and rfind
-    let mut lowest = None;
-    let mut left_num = 0;
-    for num in allow_list {
-        let index = match line.find(num) {
-            Some(x) => x,
-            None => continue,
-        };
+// ====================================================
+//                      Report
+// ====================================================
+
+// what was found on one line of the document
+struct Reading<'a> {
+    line: usize,
+    tokens: Option<(Token<'a>, Token<'a>)>,
+}
+
+impl Reading<'_> {
+    fn value(&self) -> u32 {
+        self.tokens
+            .as_ref()
+            .map_or(0, |(left, right)| left.value * 10 + right.value)
+    }
+}
+
+fn read_lines<'a, S: AsRef<str>>(lines: &[S], vocabulary: &'a Vocabulary) -> Vec<Reading<'a>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| Reading {
+            line: index + 1,
+            tokens: vocabulary.first_and_last(line.as_ref()),
+        })
+        .collect()
+}
+
+const REPORT_HEADER: [&str; 7] = ["line", "left", "at", "right", "at", "value", "note"];
+
+// one row per line, then how many lines were short of digits
+fn report(readings: &[Reading]) -> String {
+    let rows: Vec<[String; 7]> = readings
+        .iter()
+        .map(|reading| match &reading.tokens {
+            Some((left, right)) => [
+                reading.line.to_string(),
+                left.word.to_string(),
+                left.start.to_string(),
+                right.word.to_string(),
+                right.start.to_string(),
+                reading.value().to_string(),
+                String::new(),
+            ],
+            None => [
+                reading.line.to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "0".to_string(),
+                "no digits".to_string(),
+            ],
+        })
+        .collect();
+    let mut out = render_table(REPORT_HEADER, &rows);
 
-        if let Some(low_index) = lowest {
-            if index < low_index {
-                lowest = Some(index);
-                left_num = translate_num_to_int(num);
+    let missing = readings
+        .iter()
+        .filter(|reading| reading.tokens.is_none())
+        .count();
+    let calibration: u32 = readings.iter().map(Reading::value).sum();
+    out.push_str(&format!(
+        "{} lines, {missing} without digits, calibration value {calibration}\n",
+        readings.len()
+    ));
+    out
+}
+
+// ====================================================
+//                     Vocabulary
+// ====================================================
+
+// a word found in a line, and the byte it starts at
+#[derive(Debug, PartialEq)]
+struct Token<'a> {
+    word: &'a str,
+    value: u32,
+    start: usize,
+}
+
+// the words a line can spell its digits with and what each is worth
+struct Vocabulary {
+    scanner: Scanner,
+    words: Vec<String>,
+    values: Vec<u32>,
+    ignore_case: bool,
+}
+
+impl Vocabulary {
+    fn new(entries: Vec<(String, u32)>, ignore_case: bool) -> Result<Self> {
+        let mut words: Vec<String> = vec![];
+        let mut values: Vec<u32> = vec![];
+        let mut seen: HashMap<String, u32> = HashMap::new();
+        for (word, value) in entries {
+            let word = if ignore_case {
+                word.to_ascii_lowercase()
+            } else {
+                word
+            };
+            match seen.get(&word) {
+                Some(&existing) if existing != value => {
+                    return Err(anyhow!("{word} is worth both {existing} and {value}"));
+                }
+                Some(_) => continue,
+                None => {
+                    seen.insert(word.clone(), value);
+                    words.push(word);
+                    values.push(value);
+                }
             }
-        } else {
-            lowest = Some(index);
-            left_num = translate_num_to_int(num);
         }
+
+        Ok(Self {
+            scanner: Scanner::new(&words),
+            words,
+            values,
+            ignore_case,
+        })
     }
 
-    let mut highest = None;
-    let mut right_num = 0;
-    for num in allow_list {
-        let index = match line.rfind(num) {
-            Some(x) => x,
-            None => continue,
+    // the words starting furthest left and furthest right. Only ascii letters
+    // are folded, which keeps every byte where it was so the starts still
+    // point into the original line
+    fn first_and_last(&self, line: &str) -> Option<(Token<'_>, Token<'_>)> {
+        let found = if self.ignore_case {
+            self.scanner.first_and_last(&line.to_ascii_lowercase())
+        } else {
+            self.scanner.first_and_last(line)
         };
+        let token = |(start, pattern): (usize, usize)| Token {
+            word: &self.words[pattern],
+            value: self.values[pattern],
+            start,
+        };
+        found.map(|(first, last)| (token(first), token(last)))
+    }
+}
+
+fn parse_table(table: &str) -> Result<Vec<(String, u32)>> {
+    table
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(
+            |(index, line)| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [word, value] => {
+                    let value = value
+                        .parse::<u32>()
+                        .with_context(|| format!("value {value:?} on line {}", index + 1))?;
+                    // each word stands for one digit of a two digit number
+                    if value > 9 {
+                        return Err(anyhow!(
+                            "value {value} on line {} is not a single digit",
+                            index + 1
+                        ));
+                    }
+                    Ok((word.to_string(), value))
+                }
+                _ => Err(anyhow!(
+                    "expected \"word value\" on line {}, found {line:?}",
+                    index + 1
+                )),
+            },
+        )
+        .collect()
+}
+
+// ====================================================
+//                   Aho-Corasick
+// ====================================================
 
-        if let Some(high_index) = highest {
-            if index > high_index {
-                highest = Some(index);
-                right_num = translate_num_to_int(num);
+// a trie of every pattern, where each node also knows the longest proper
+// suffix of its text that's in the trie, so a line is scanned once for
+// all of them, overlaps included: "eightwo" finds eight and then two
+struct Scanner {
+    // the length of each pattern, all the scan needs of them
+    lengths: Vec<usize>,
+    children: Vec<HashMap<u8, usize>>,
+    // where to carry on from when a node has no child for the next byte
+    fail: Vec<usize>,
+    // every pattern ending at a node, its own and those of its fail chain
+    matches: Vec<Vec<usize>>,
+}
+
+impl Scanner {
+    fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut children = vec![HashMap::new()];
+        let mut matches = vec![vec![]];
+        for (index, pattern) in patterns.iter().enumerate() {
+            let mut node = 0;
+            for byte in pattern.as_ref().bytes() {
+                node = match children[node].get(&byte) {
+                    Some(child) => *child,
+                    None => {
+                        children.push(HashMap::new());
+                        matches.push(vec![]);
+                        let child = children.len() - 1;
+                        children[node].insert(byte, child);
+                        child
+                    }
+                };
             }
-        } else {
-            highest = Some(index);
-            right_num = translate_num_to_int(num);
+            matches[node].push(index);
+        }
+
+        // breadth first, so a node's fail target is always finished first
+        let mut fail = vec![0; children.len()];
+        let mut queue: VecDeque<usize> = children[0].values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            for (&byte, &child) in &children[node] {
+                let mut target = fail[node];
+                while target != 0 && !children[target].contains_key(&byte) {
+                    target = fail[target];
+                }
+                fail[child] = match children[target].get(&byte) {
+                    Some(&next) if next != child => next,
+                    _ => 0,
+                };
+                let inherited = matches[fail[child]].clone();
+                matches[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Self {
+            lengths: patterns
+                .iter()
+                .map(|pattern| pattern.as_ref().len())
+                .collect(),
+            children,
+            fail,
+            matches,
         }
     }
 
-    (left_num, right_num)
+    // the start and index of the patterns starting furthest left and furthest
+    // right. Ties on the start go to whichever comes first in the pattern list
+    fn first_and_last(&self, line: &str) -> Option<((usize, usize), (usize, usize))> {
+        let mut first: Option<(usize, usize)> = None;
+        let mut last: Option<(usize, usize)> = None;
+        let mut node = 0;
+        for (end, byte) in line.bytes().enumerate() {
+            while node != 0 && !self.children[node].contains_key(&byte) {
+                node = self.fail[node];
+            }
+            node = self.children[node].get(&byte).copied().unwrap_or(0);
+            for &pattern in &self.matches[node] {
+                let start = end + 1 - self.lengths[pattern];
+                if first.is_none_or(|first| (start, pattern) < first) {
+                    first = Some((start, pattern));
+                }
+                if last.is_none_or(|(last_start, last_pattern)| {
+                    start > last_start || (start == last_start && pattern < last_pattern)
+                }) {
+                    last = Some((start, pattern));
+                }
+            }
+        }
+
+        Some((first?, last?))
+    }
 }
 
-fn translate_num_to_int(num: &str) -> u32 {
-    match num {
-        "one" | "1" => 1,
-        "two" | "2" => 2,
-        "three" | "3" => 3,
-        "four" | "4" => 4,
-        "five" | "5" => 5,
-        "six" | "6" => 6,
-        "seven" | "7" => 7,
-        "eight" | "8" => 8,
-        "nine" | "9" => 9,
-        _ => panic!("don't do that"),
+#[cfg(test)]
+mod test {
+    use crate::{parse_table, read_lines, report, Scanner, Token, Vocabulary, DIGITS, ENGLISH};
+
+    fn vocabulary(tables: &[&str], ignore_case: bool) -> Vocabulary {
+        let entries = tables
+            .iter()
+            .flat_map(|table| parse_table(table).unwrap())
+            .collect();
+        Vocabulary::new(entries, ignore_case).unwrap()
+    }
+
+    fn calibration(s: &str, vocabulary: &Vocabulary) -> u32 {
+        let lines: Vec<&str> = s.lines().collect();
+        read_lines(&lines, vocabulary)
+            .iter()
+            .map(|reading| reading.value())
+            .sum()
+    }
+
+    fn find_num_pairs(line: &str, vocabulary: &Vocabulary) -> (u32, u32) {
+        vocabulary
+            .first_and_last(line)
+            .map_or((0, 0), |(left, right)| (left.value, right.value))
+    }
+
+    #[test]
+    fn day1_calibration_example() {
+        let s = "1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet";
+        assert_eq!(calibration(s, &vocabulary(&[DIGITS], false)), 142);
+
+        let s = "two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen";
+        assert_eq!(calibration(s, &vocabulary(&[DIGITS, ENGLISH], false)), 281);
+    }
+
+    #[test]
+    fn day1_overlapping_words() {
+        let english = vocabulary(&[DIGITS, ENGLISH], false);
+        assert_eq!(find_num_pairs("eightwo", &english), (8, 2));
+        assert_eq!(find_num_pairs("oneight", &english), (1, 8));
+        assert_eq!(find_num_pairs("twone", &english), (2, 1));
+        assert_eq!(find_num_pairs("xoneightwonex", &english), (1, 1));
+        assert_eq!(find_num_pairs("sevenine", &english), (7, 9));
+        // a failed "seve" still has to find the "eight" inside "seveight"
+        assert_eq!(find_num_pairs("seveight", &english), (8, 8));
+        assert_eq!(find_num_pairs("nothing", &english), (0, 0));
+
+        // patterns inside other patterns
+        let scanner = Scanner::new(&["he", "she", "hers", "his"]);
+        assert_eq!(scanner.first_and_last("ushers"), Some(((1, 1), (2, 0))));
+        assert_eq!(scanner.first_and_last("ahishe"), Some(((1, 3), (4, 0))));
+        // he and hers both start at 2, the list order breaks the tie
+        let scanner = Scanner::new(&["hers", "he"]);
+        assert_eq!(scanner.first_and_last("ushers"), Some(((2, 0), (2, 0))));
+    }
+
+    #[test]
+    fn day1_vocabularies() {
+        let german = "# zahlen
+null 0
+eins 1
+zwei 2
+drei 3
+
+neun 9";
+        let words = vocabulary(&[DIGITS, german], false);
+        assert_eq!(find_num_pairs("xnulleinsdrei", &words), (0, 3));
+        assert_eq!(find_num_pairs("ZWEI4neun", &words), (4, 9));
+        let words = vocabulary(&[DIGITS, german], true);
+        assert_eq!(find_num_pairs("ZWEI4Neun", &words), (2, 9));
+        // non-ascii letters aren't folded, so nothing before them moves
+        let words = vocabulary(&[DIGITS, "zwölf 2\nfünf 5"], true);
+        let (left, right) = words.first_and_last("İ ZWÖLF zwölf FÜNF").unwrap();
+        assert_eq!((left.word, left.start), ("zwölf", 10));
+        assert_eq!(right.start, 10);
+
+        let ordinals = vocabulary(&["first 1\nsecond 2\nthird 3"], false);
+        assert_eq!(find_num_pairs("thirdsecondfirst", &ordinals), (3, 1));
+
+        // the same word twice is fine, with two values it's not
+        assert!(Vocabulary::new(parse_table("one 1\none 1").unwrap(), false).is_ok());
+        let err = Vocabulary::new(parse_table("One 1\none 2").unwrap(), true).err();
+        assert_eq!(err.unwrap().to_string(), "one is worth both 1 and 2");
+
+        let err = parse_table("one 1\ntwo").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected \"word value\" on line 2, found \"two\""
+        );
+        assert!(parse_table("one -1").is_err());
+        let err = parse_table("one 1\nten 10").unwrap_err();
+        assert_eq!(err.to_string(), "value 10 on line 2 is not a single digit");
+    }
+
+    #[test]
+    fn day1_report() {
+        let english = vocabulary(&[DIGITS, ENGLISH], false);
+        let lines = ["two1nine", "no numbers here", "7pqrstsixteen", "xoneightx"];
+        let readings = read_lines(&lines, &english);
+        assert_eq!(
+            readings[3].tokens,
+            Some((
+                Token {
+                    word: "one",
+                    value: 1,
+                    start: 1
+                },
+                Token {
+                    word: "eight",
+                    value: 8,
+                    start: 3
+                }
+            ))
+        );
+        assert!(readings[1].tokens.is_none());
+        assert_eq!(
+            report(&readings),
+            "line  left  at  right  at  value  note
+1     two   0   nine   4   29
+2     -     -   -      -   0      no digits
+3     7     0   six    6   76
+4     one   1   eight  3   18
+4 lines, 1 without digits, calibration value 123
+"
+        );
     }
 }
@@ -1,32 +1,58 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use anyhow::{anyhow, Result};
-use aoc::{Part, Pattern};
+use aoc::{overflow, Part, Pattern};
+use clap::{arg, ArgAction};
 
+const LIMITS: &str = "red=12,green=13,blue=14";
+
+// what to do with a color listed twice in one round, like "3 blue, 4 blue"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Duplicates {
+    Sum,
+    Reject,
+}
+
+impl Duplicates {
+    fn new(policy: &str) -> Result<Self> {
+        match policy {
+            "sum" => Ok(Duplicates::Sum),
+            "reject" => Ok(Duplicates::Reject),
+            _ => Err(anyhow!(
+                "unknown repeated colors policy {policy}, expected reject or sum"
+            )),
+        }
+    }
+}
+
+// how many cubes of each color, a color that isn't there has none
+#[derive(Default, Debug, PartialEq)]
 struct Count {
-    reds: u32,
-    greens: u32,
-    blues: u32,
+    cubes: BTreeMap<String, u32>,
 }
 
 impl Count {
-    fn new(reds: u32, greens: u32, blues: u32) -> Self {
-        Self {
-            reds,
-            greens,
-            blues,
-        }
+    fn get(&self, color: &str) -> u32 {
+        self.cubes.get(color).copied().unwrap_or(0)
     }
 
-    fn power(&self) -> u32 {
-        self.reds * self.blues * self.greens
+    fn power(&self) -> Result<u64> {
+        self.cubes
+            .values()
+            .try_fold(1u64, |power, number| power.checked_mul(*number as u64))
+            .ok_or_else(|| overflow::<u64>(format!("power of {self}")))
     }
 }
 
 // for debugging
 impl Display for Count {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "R:{} G:{} B:{}", self.reds, self.greens, self.blues,)
+        let cubes = self
+            .cubes
+            .iter()
+            .map(|(color, number)| format!("{color}:{number}"))
+            .collect::<Vec<String>>();
+        write!(f, "{}", cubes.join(" "))
     }
 }
 
@@ -40,69 +66,206 @@ impl Game {
         Self { id, rounds }
     }
 
-    fn find_smallest_possible_count(&self) -> Count {
-        let mut reds = 0;
-        let mut blues = 0;
-        let mut greens = 0;
-        // find the 3 maxes of each color in any round played in a game
+    // the max of each color in any round played in a game. Every color
+    // in the rules is counted, so one the game never shows has a max of 0
+    fn find_smallest_possible_count(&self, rules: &Count) -> Count {
+        let mut smallest = Count::default();
+        for color in rules.cubes.keys() {
+            smallest.cubes.insert(color.clone(), 0);
+        }
         for count in &self.rounds[..] {
-            if count.reds > reds {
-                reds = count.reds;
-            }
-            if count.blues > blues {
-                blues = count.blues;
-            }
-            if count.greens > greens {
-                greens = count.greens;
+            for (color, number) in &count.cubes {
+                let max = smallest.cubes.entry(color.clone()).or_insert(0);
+                *max = (*max).max(*number);
             }
         }
 
-        Count::new(reds, greens, blues)
+        smallest
     }
 
+    // colors the rules don't mention can't be in the bag at all
     fn is_possible(&self, rules: &Count) -> bool {
         for round in &self.rounds[..] {
-            if round.blues > rules.blues || round.reds > rules.reds || round.greens > rules.greens {
+            if round
+                .cubes
+                .iter()
+                .any(|(color, number)| *number > rules.get(color))
+            {
                 return false;
             }
         }
 
         true
     }
+
+    // every cube count in the game over its color's limit
+    fn violations(&self, rules: &Count) -> Vec<Violation> {
+        let mut violations = vec![];
+        for (index, round) in self.rounds.iter().enumerate() {
+            for (color, number) in &round.cubes {
+                if *number > rules.get(color) {
+                    violations.push(Violation {
+                        game: self.id,
+                        round: index + 1,
+                        color: color.clone(),
+                        count: *number,
+                        limit: rules.get(color),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
 }
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--limit <limits> "most cubes of each color (default red=12,green=13,blue=14)"),
+        arg!(--"repeated-colors" <policy> "reject (default) or sum a color given twice in a round"),
+        arg!(--report "list impossible games, the spread of each color and the tightest limits")
+            .action(ArgAction::SetTrue),
+    ])?;
     let mut reader = aoc::open_into_buffered_reader(&problem.path)?;
 
-    let max_cube_rule = Count::new(12, 13, 14);
+    let max_cube_rule = parse_limits(
+        matches
+            .get_one::<String>("limit")
+            .map_or(LIMITS, |limits| &limits[..]),
+    )?;
+    let duplicates = matches
+        .get_one::<String>("repeated-colors")
+        .map_or(Ok(Duplicates::Reject), |policy| Duplicates::new(policy))?;
 
     let mut sum_ids = 0;
 
     let mut games = vec![];
     while let Some(line) = aoc::get_line(&mut reader) {
-        let game = parse_game(&line).expect("could not parse game");
+        let game = parse_game(&line, duplicates)?;
         if game.is_possible(&max_cube_rule) {
             sum_ids += game.id;
         }
         games.push(game);
     }
     println!("sum ids is {sum_ids}");
+    if matches.get_flag("report") {
+        print!("{}", report(&games, &max_cube_rule));
+    }
 
     if problem.part == Part::P1 {
         return Ok(());
     }
 
-    let mut sum_powers = 0;
-    for game in games {
-        let count = game.find_smallest_possible_count();
-        sum_powers += count.power();
+    let mut sum_powers = 0u64;
+    for game in &games {
+        let count = game.find_smallest_possible_count(&max_cube_rule);
+        let power = count
+            .power()
+            .map_err(|err| anyhow!("game {}, {err:#}", game.id))?;
+        sum_powers = sum_powers
+            .checked_add(power)
+            .ok_or_else(|| overflow::<u64>(format!("sum of powers up to game {}", game.id)))?;
     }
     println!("sum powers is {sum_powers}");
 
     Ok(())
 }
 
+// ====================================================
+//                     Statistics
+// ====================================================
+
+// a round that drew more of a color than the bag can hold
+#[derive(Debug, PartialEq)]
+struct Violation {
+    game: u32,
+    round: usize,
+    color: String,
+    count: u32,
+    limit: u32,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "game {} round {}: {} {} over the limit of {}",
+            self.game, self.round, self.count, self.color, self.limit
+        )
+    }
+}
+
+// why each impossible game is impossible, in input order
+fn impossible_games(games: &[Game], rules: &Count) -> Vec<Violation> {
+    games
+        .iter()
+        .filter(|game| !game.is_possible(rules))
+        .flat_map(|game| game.violations(rules))
+        .collect()
+}
+
+// for each color, how many games needed at least so many cubes of it
+fn max_distribution(games: &[Game], rules: &Count) -> BTreeMap<String, BTreeMap<u32, usize>> {
+    let mut distribution: BTreeMap<String, BTreeMap<u32, usize>> = BTreeMap::new();
+    for game in games {
+        for (color, max) in game.find_smallest_possible_count(rules).cubes {
+            *distribution
+                .entry(color)
+                .or_default()
+                .entry(max)
+                .or_default() += 1;
+        }
+    }
+
+    distribution
+}
+
+// the smallest bag every game could have been played with
+fn minimum_limits(games: &[Game], rules: &Count) -> Count {
+    let mut limits = Count::default();
+    for game in games {
+        for (color, max) in game.find_smallest_possible_count(rules).cubes {
+            let limit = limits.cubes.entry(color).or_insert(0);
+            *limit = (*limit).max(max);
+        }
+    }
+
+    limits
+}
+
+fn report(games: &[Game], rules: &Count) -> String {
+    let mut out = String::new();
+    let violations = impossible_games(games, rules);
+    if violations.is_empty() {
+        out.push_str("every game is possible\n");
+    } else {
+        out.push_str("impossible games:\n");
+        for violation in &violations {
+            out.push_str(&format!("  {violation}\n"));
+        }
+    }
+
+    out.push_str("most cubes needed per game, by color:\n");
+    for (color, maxes) in max_distribution(games, rules) {
+        let maxes = maxes
+            .iter()
+            .map(|(max, games)| format!("{max} x{games}"))
+            .collect::<Vec<String>>();
+        out.push_str(&format!("  {color}: {}\n", maxes.join(", ")));
+    }
+
+    let limits = minimum_limits(games, rules)
+        .cubes
+        .iter()
+        .map(|(color, limit)| format!("{color}={limit}"))
+        .collect::<Vec<String>>();
+    out.push_str(&format!(
+        "every game fits under --limit {}\n",
+        limits.join(",")
+    ));
+    out
+}
+
 fn game_id(game_header: &str) -> Result<u32> {
     let capture = aoc::search(game_header, vec![Pattern::Number])?;
     let game_id = capture
@@ -112,46 +275,240 @@ fn game_id(game_header: &str) -> Result<u32> {
     Ok(game_id.parse()?)
 }
 
-fn parse_game(line: &str) -> Result<Game> {
+fn parse_game(line: &str, duplicates: Duplicates) -> Result<Game> {
     let split = line.split(":").collect::<Vec<&str>>();
-    let game_header = split.get(0).ok_or(anyhow!("no game header"))?;
+    let game_header = split.first().ok_or(anyhow!("no game header"))?;
     let game_rounds = split.get(1).ok_or(anyhow!("no game rounds"))?;
-    let id = game_id(&game_header)?;
-    let rounds = parse_gameplay(game_rounds)?;
+    let id = game_id(game_header)?;
+    let rounds =
+        parse_gameplay(game_rounds, duplicates).map_err(|err| anyhow!("game {id}, {err:#}"))?;
 
     Ok(Game::new(id, rounds))
 }
 
-fn parse_gameplay(game_rounds: &str) -> Result<Vec<Count>> {
+fn parse_gameplay(game_rounds: &str, duplicates: Duplicates) -> Result<Vec<Count>> {
     let rounds = game_rounds.split(";").collect::<Vec<&str>>();
     let mut color_counts = vec![];
-    for round in rounds {
-        color_counts.push(parse_round(round)?);
+    for (index, round) in rounds.iter().enumerate() {
+        let count = parse_round(round, duplicates)
+            .map_err(|err| anyhow!("round {}: {err:#}", index + 1))?;
+        color_counts.push(count);
     }
 
     Ok(color_counts)
 }
 
-fn parse_round(game_round: &str) -> Result<Count> {
-    let mut reds = 0;
-    let mut blues = 0;
-    let mut greens = 0;
+fn parse_round(game_round: &str, duplicates: Duplicates) -> Result<Count> {
+    if game_round.trim().is_empty() {
+        return Err(anyhow!("empty round"));
+    }
 
+    let mut count = Count::default();
     for cube in game_round.split(",") {
+        if cube.trim().is_empty() {
+            return Err(anyhow!("missing cubes in {:?}", game_round.trim()));
+        }
         let capture = aoc::search(cube, vec![Pattern::Number, Pattern::Space, Pattern::Word])?;
         let color = capture.name("word").ok_or(anyhow!("no word"))?.as_str();
         let number = capture.name("number").ok_or(anyhow!("no number"))?.as_str();
-        match color {
-            "blue" => blues = number.parse::<u32>()?,
-            "red" => reds = number.parse::<u32>()?,
-            "green" => greens = number.parse::<u32>()?,
-            _ => {
-                return Err(anyhow!(
-                    "no color match for cube count in round {game_round}"
-                ))
+        let number = number.parse::<u32>()?;
+        match count.cubes.get_mut(color) {
+            Some(total) if duplicates == Duplicates::Sum => {
+                *total = total.checked_add(number).ok_or_else(|| {
+                    overflow::<u32>(format!("{color} total of {total} + {number}"))
+                })?;
+            }
+            Some(_) => return Err(anyhow!("{color} listed more than once")),
+            None => {
+                count.cubes.insert(color.to_string(), number);
             }
         }
     }
 
-    Ok(Count::new(reds, greens, blues))
+    Ok(count)
+}
+
+// e.g. red=12,green=13,blue=14
+fn parse_limits(limits: &str) -> Result<Count> {
+    let mut count = Count::default();
+    for limit in limits.split(",") {
+        let (color, number) = limit
+            .split_once("=")
+            .ok_or(anyhow!("expected color=number in limit {limit:?}"))?;
+        let number = number
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| anyhow!("limit {limit:?}: {err}"))?;
+        let color = color.trim();
+        if color.is_empty() {
+            return Err(anyhow!("missing color in limit {limit:?}"));
+        }
+        if count.cubes.insert(color.to_string(), number).is_some() {
+            return Err(anyhow!("{color} listed more than once in the limits"));
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        impossible_games, max_distribution, minimum_limits, parse_game, parse_limits, report,
+        Count, Duplicates, Game, Violation, LIMITS,
+    };
+    use aoc::Overflow;
+
+    const GAMES: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    fn games() -> Vec<Game> {
+        GAMES
+            .lines()
+            .map(|line| parse_game(line, Duplicates::Reject).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn day2_cube_example() {
+        let rules = parse_limits(LIMITS).unwrap();
+        let games = games();
+        let sum_ids: u32 = games
+            .iter()
+            .filter(|game| game.is_possible(&rules))
+            .map(|game| game.id)
+            .sum();
+        assert_eq!(sum_ids, 8);
+        let sum_powers: u64 = games
+            .iter()
+            .map(|game| game.find_smallest_possible_count(&rules).power().unwrap())
+            .sum();
+        assert_eq!(sum_powers, 2286);
+    }
+
+    #[test]
+    fn day2_other_colors() {
+        let rules = parse_limits("red=2, yellow=5").unwrap();
+        assert_eq!(rules.to_string(), "red:2 yellow:5");
+
+        let game = parse_game("Game 7: 5 yellow, 1 red; 2 red", Duplicates::Reject).unwrap();
+        assert!(game.is_possible(&rules));
+        let smallest = game.find_smallest_possible_count(&rules);
+        assert_eq!(smallest, rules);
+        assert_eq!(smallest.power().unwrap(), 10);
+
+        // blue isn't allowed at all, and a limit color never drawn zeroes the power
+        let game = parse_game("Game 8: 1 blue, 2 red", Duplicates::Reject).unwrap();
+        assert!(!game.is_possible(&rules));
+        let smallest = game.find_smallest_possible_count(&rules);
+        assert_eq!(smallest.get("blue"), 1);
+        assert_eq!(smallest.power().unwrap(), 0);
+
+        // three colors at u32::MAX multiply past u64
+        let game = parse_game(
+            "Game 9: 4294967295 red, 4294967295 green, 4294967295 blue",
+            Duplicates::Reject,
+        )
+        .unwrap();
+        let smallest = game.find_smallest_possible_count(&Count::default());
+        let err = smallest.power().unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert_eq!(
+            err.to_string(),
+            "power of blue:4294967295 green:4294967295 red:4294967295 overflows u64"
+        );
+
+        assert!(parse_limits("red").is_err());
+        assert!(parse_limits("red=lots").is_err());
+        let err = parse_limits("red=12,red=99").unwrap_err();
+        assert_eq!(err.to_string(), "red listed more than once in the limits");
+        let err = parse_limits("=5").unwrap_err();
+        assert_eq!(err.to_string(), "missing color in limit \"=5\"");
+    }
+
+    #[test]
+    fn day2_round_validation() {
+        let line = "Game 4: 1 red; 3 blue, 2 green, 4 blue";
+        let err = parse_game(line, Duplicates::Reject).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "game 4, round 2: blue listed more than once"
+        );
+        let game = parse_game(line, Duplicates::Sum).unwrap();
+        assert_eq!(game.rounds[1].get("blue"), 7);
+        let err = parse_game("Game 3: 1 red; 4294967295 blue, 1 blue", Duplicates::Sum)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "game 3, round 2: blue total of 4294967295 + 1 overflows u32"
+        );
+        assert_eq!(game.rounds[1].get("green"), 2);
+
+        let err = parse_game("Game 5: 1 red;; 2 red", Duplicates::Sum)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "game 5, round 2: empty round");
+        let err = parse_game("Game 6:", Duplicates::Sum).err().unwrap();
+        assert_eq!(err.to_string(), "game 6, round 1: empty round");
+        let err = parse_game("Game 7: 1 red, , 2 blue", Duplicates::Sum)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "game 7, round 1: missing cubes in \"1 red, , 2 blue\""
+        );
+        let err = parse_game("Game 8: 1 red; blue", Duplicates::Sum)
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("game 8, round 2: "), "{err}");
+    }
+
+    #[test]
+    fn day2_statistics() {
+        let rules = parse_limits(LIMITS).unwrap();
+        let games = games();
+        let violations = impossible_games(&games, &rules);
+        assert_eq!(
+            violations[0],
+            Violation {
+                game: 3,
+                round: 1,
+                color: "red".to_string(),
+                count: 20,
+                limit: 12,
+            }
+        );
+        assert_eq!(violations.len(), 3);
+
+        let distribution = max_distribution(&games, &rules);
+        assert_eq!(
+            distribution["red"].iter().collect::<Vec<_>>(),
+            [(&1, &1), (&4, &1), (&6, &1), (&14, &1), (&20, &1)]
+        );
+        assert_eq!(distribution["green"][&3], 3);
+        assert_eq!(
+            minimum_limits(&games, &rules).to_string(),
+            "blue:15 green:13 red:20"
+        );
+
+        assert_eq!(
+            report(&games, &rules),
+            "impossible games:
+  game 3 round 1: 20 red over the limit of 12
+  game 4 round 3: 15 blue over the limit of 14
+  game 4 round 3: 14 red over the limit of 12
+most cubes needed per game, by color:
+  blue: 2 x1, 4 x1, 6 x2, 15 x1
+  green: 2 x1, 3 x3, 13 x1
+  red: 1 x1, 4 x1, 6 x1, 14 x1, 20 x1
+every game fits under --limit blue=15,green=13,red=20
+"
+        );
+        let rules = minimum_limits(&games, &rules);
+        assert!(report(&games, &rules).starts_with("every game is possible\n"));
+    }
 }
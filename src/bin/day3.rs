@@ -1,111 +1,242 @@
-use std::{cell::RefCell, collections::HashMap, fs, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use aoc::overflow;
+use clap::{arg, ArgAction};
 
 type Point = (usize, usize);
 type Range = (usize, usize);
 
-type PartNumber = Rc<RefCell<u32>>;
-
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
-    let schematic = fs::read_to_string(&problem.path)?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--symbols <chars> "characters that count as symbols (default all but digits and .)"),
+        arg!(--gear <char> "the symbol that can be a gear (default *)"),
+        arg!(--arity <count> "how many part numbers a gear touches (default 2)"),
+        arg!(--orphans "list the numbers that touch no symbol").action(ArgAction::SetTrue),
+    ])?;
+    let schematic = Schematic::parse(&fs::read_to_string(&problem.path)?)?;
 
-    println!("sum is: {:?}", sum_engine_schematic(&schematic));
+    let mut rules = Rules::standard();
+    if let Some(symbols) = matches.get_one::<String>("symbols") {
+        rules.symbols = Some(symbols.chars().collect());
+    }
+    if let Some(gear) = matches.get_one::<String>("gear") {
+        let mut chars = gear.chars();
+        rules.gear = match (chars.next(), chars.next()) {
+            (Some(gear), None) => gear,
+            _ => return Err(anyhow!("a gear is one character, not {gear:?}")),
+        };
+    }
+    if let Some(arity) = matches.get_one::<String>("arity") {
+        rules.gear_arity = arity.parse().context("gear arity")?;
+    }
+
+    if matches.get_flag("orphans") {
+        for orphan in schematic.orphans(&rules) {
+            println!(
+                "orphan {} at row {}, columns {}..{}",
+                orphan.value, orphan.row, orphan.span.0, orphan.span.1
+            );
+        }
+    }
+    let part_sum: u64 = schematic
+        .parts(&rules)
+        .iter()
+        .map(|part| part.number.value as u64)
+        .sum();
+    let gear_ratio_sum: u64 = schematic.gears(&rules)?.iter().map(|gear| gear.ratio).sum();
+    println!("sum is: {:?}", (part_sum, gear_ratio_sum));
 
     Ok(())
 }
 
-fn sum_engine_schematic(schematic: &str) -> (u32, u32) {
-    let mut point_map: HashMap<Point, PartNumber> = HashMap::new();
-    let mut x_start = None;
-    let mut num = String::new();
-
-    let mut parsed_schematic = vec![];
-    for (y, line) in schematic.lines().filter(|s| s.len() > 0).enumerate() {
-        let mut parsed_line = vec![];
-        for (x, ch) in line.chars().enumerate() {
-            if ch.is_ascii_digit() {
-                num.push(ch);
-                // start tracking
-                if None == x_start {
-                    x_start = Some(x);
-                }
-            } else {
-                x_start = x_start.and_then(|x_start| {
-                    let value = num.parse::<u32>().expect(&format!("bad num val: {num}"));
-                    add_to_point_map(&mut point_map, (x_start, x), y, value);
-                    num.clear();
+// ====================================================
+//                     Schematic
+// ====================================================
 
-                    None
-                });
-            }
-            parsed_line.push(ch);
-        }
-        // repeat opp for end
-        if num.len() > 0 {
-            x_start = x_start.and_then(|x_start| {
-                let value = num.parse().expect(&format!("bad num val: {num}"));
-                add_to_point_map(&mut point_map, (x_start, parsed_line.len()), y, value);
-                num.clear();
-
-                None
-            });
-        }
+// what counts as a symbol, and which symbol with how many part numbers
+// around it makes a gear
+struct Rules {
+    // None for anything that isn't a digit or a .
+    symbols: Option<HashSet<char>>,
+    gear: char,
+    gear_arity: usize,
+}
 
-        parsed_schematic.push(parsed_line);
+impl Rules {
+    fn standard() -> Self {
+        Self {
+            symbols: None,
+            gear: '*',
+            gear_arity: 2,
+        }
     }
 
-    let mut part_number_sum = 0;
-    let mut gear_ratio_sum = 0;
-    for (y, line) in parsed_schematic.iter().enumerate() {
-        for (x, point) in line.iter().enumerate() {
-            // PART 1
-            if !point.is_ascii_digit() && *point != '.' {
-                let adj_nums =
-                    find_all_nums_adj((x, y), (line.len(), parsed_schematic.len()), &mut point_map);
-                part_number_sum += adj_nums.iter().sum::<u32>();
-            }
-            // PART 2
-            if *point == '*' {
-                let adj_nums =
-                    find_all_nums_adj((x, y), (line.len(), parsed_schematic.len()), &mut point_map);
-                if adj_nums.len() == 2 {
-                    gear_ratio_sum += adj_nums.iter().product::<u32>()
-                }
-            }
+    fn is_symbol(&self, ch: char) -> bool {
+        match &self.symbols {
+            Some(symbols) => symbols.contains(&ch),
+            None => !ch.is_ascii_digit() && ch != '.',
         }
     }
+}
+
+// a run of digits, spanning columns start..end of its row
+#[derive(Debug, PartialEq)]
+struct Number {
+    value: u32,
+    row: usize,
+    span: Range,
+}
+
+// a number touching at least one symbol
+#[derive(Debug, PartialEq)]
+struct Part<'a> {
+    number: &'a Number,
+    symbols: Vec<(char, Point)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Gear {
+    position: Point,
+    parts: Vec<u32>,
+    ratio: u64,
+}
 
-    (part_number_sum, gear_ratio_sum)
+struct Schematic {
+    grid: Vec<Vec<char>>,
+    numbers: Vec<Number>,
+    // which number, by index, covers each point
+    number_at: HashMap<Point, usize>,
 }
 
-fn find_all_nums_adj(
-    point: Point,
-    bounds: Range,
-    point_map: &mut HashMap<Point, PartNumber>,
-) -> Vec<u32> {
-    let mut part_numbers: Vec<PartNumber> = vec![];
-    let adj_points = adjacent_points(point, bounds);
-    for point in adj_points {
-        if let Some(part_number) = point_map.get(&point) {
-            if is_new_part(&part_numbers, &part_number) {
-                part_numbers.push(part_number.clone());
+impl Schematic {
+    fn parse(schematic: &str) -> Result<Self> {
+        let grid: Vec<Vec<char>> = schematic
+            .lines()
+            .filter(|s| !s.is_empty())
+            .map(|line| line.chars().collect())
+            .collect();
+
+        let mut numbers = vec![];
+        let mut number_at = HashMap::new();
+        for (y, line) in grid.iter().enumerate() {
+            let mut x = 0;
+            while x < line.len() {
+                if !line[x].is_ascii_digit() {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < line.len() && line[x].is_ascii_digit() {
+                    x += 1;
+                }
+                let digits: String = line[start..x].iter().collect();
+                let value = digits
+                    .parse::<u32>()
+                    .with_context(|| format!("number at row {y}, column {start}"))?;
+                for column in start..x {
+                    number_at.insert((column, y), numbers.len());
+                }
+                numbers.push(Number {
+                    value,
+                    row: y,
+                    span: (start, x),
+                });
             }
         }
+
+        Ok(Self {
+            grid,
+            numbers,
+            number_at,
+        })
     }
 
-    part_numbers.iter().map(|part| *part.borrow()).collect()
-}
+    fn bounds(&self) -> Range {
+        let width = self.grid.iter().map(|line| line.len()).max().unwrap_or(0);
+        (width, self.grid.len())
+    }
 
-fn is_new_part(parts_seen: &Vec<PartNumber>, part: &PartNumber) -> bool {
-    for p in parts_seen {
-        if Rc::ptr_eq(&p, &part) {
-            return false;
-        }
+    fn at(&self, (x, y): Point) -> Option<char> {
+        self.grid.get(y)?.get(x).copied()
     }
 
-    true
+    // the symbols around a number, each once, in reading order
+    fn symbols_around(&self, number: &Number, rules: &Rules) -> Vec<(char, Point)> {
+        let mut around: Vec<Point> = (number.span.0..number.span.1)
+            .flat_map(|x| adjacent_points((x, number.row), self.bounds()))
+            .collect();
+        around.sort_by_key(|(x, y)| (*y, *x));
+        around.dedup();
+        around
+            .into_iter()
+            .filter_map(|point| Some((self.at(point)?, point)))
+            .filter(|(ch, _)| rules.is_symbol(*ch))
+            .collect()
+    }
+
+    // the numbers around a point, each once, in reading order
+    fn numbers_around(&self, point: Point) -> Vec<&Number> {
+        let mut around: Vec<usize> = adjacent_points(point, self.bounds())
+            .iter()
+            .filter_map(|point| self.number_at.get(point).copied())
+            .collect();
+        around.sort();
+        around.dedup();
+        around.iter().map(|index| &self.numbers[*index]).collect()
+    }
+
+    fn parts(&self, rules: &Rules) -> Vec<Part<'_>> {
+        self.numbers
+            .iter()
+            .map(|number| Part {
+                number,
+                symbols: self.symbols_around(number, rules),
+            })
+            .filter(|part| !part.symbols.is_empty())
+            .collect()
+    }
+
+    fn orphans(&self, rules: &Rules) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .filter(|number| self.symbols_around(number, rules).is_empty())
+            .collect()
+    }
+
+    // every gear symbol touching exactly the gear arity of numbers
+    fn gears(&self, rules: &Rules) -> Result<Vec<Gear>> {
+        let mut gears = vec![];
+        for (y, line) in self.grid.iter().enumerate() {
+            for (x, ch) in line.iter().enumerate() {
+                if *ch != rules.gear || !rules.is_symbol(*ch) {
+                    continue;
+                }
+                let parts: Vec<u32> = self
+                    .numbers_around((x, y))
+                    .iter()
+                    .map(|number| number.value)
+                    .collect();
+                if parts.len() != rules.gear_arity {
+                    continue;
+                }
+                let ratio = parts
+                    .iter()
+                    .try_fold(1u64, |ratio, part| ratio.checked_mul(*part as u64))
+                    .ok_or_else(|| overflow::<u64>(format!("gear ratio at row {y}, column {x}")))?;
+                gears.push(Gear {
+                    position: (x, y),
+                    parts,
+                    ratio,
+                });
+            }
+        }
+
+        Ok(gears)
+    }
 }
 
 fn adjacent_points(p: Point, size: Range) -> Vec<Point> {
@@ -145,20 +276,11 @@ fn adjacent_points(p: Point, size: Range) -> Vec<Point> {
         .collect()
 }
 
-fn add_to_point_map(map: &mut HashMap<Point, PartNumber>, range: Range, y: usize, value: u32) {
-    let value = Rc::new(RefCell::new(value));
-    for x in range.0..range.1 {
-        map.insert((x, y), value.clone());
-    }
-}
-
 #[cfg(test)]
 mod test {
-    use crate::sum_engine_schematic;
+    use crate::{Gear, Number, Rules, Schematic};
 
-    #[test]
-    fn day3_simple_schematic() {
-        let test_schematic = "467..114..
+    const SCHEMATIC: &str = "467..114..
 ...*......
 ..35..633.
 ......#...
@@ -169,8 +291,92 @@ mod test {
 ...$.*....
 .664.598..
 ";
-        let sums = sum_engine_schematic(test_schematic);
-        assert!(sums.0 == 4361, "part sum is {}", sums.0);
-        assert!(sums.1 == 467835, "gear ratio is {}", sums.1);
+
+    #[test]
+    fn day3_simple_schematic() {
+        let schematic = Schematic::parse(SCHEMATIC).unwrap();
+        let rules = Rules::standard();
+        let part_sum: u32 = schematic
+            .parts(&rules)
+            .iter()
+            .map(|part| part.number.value)
+            .sum();
+        let gear_ratio_sum: u64 = schematic
+            .gears(&rules)
+            .unwrap()
+            .iter()
+            .map(|gear| gear.ratio)
+            .sum();
+        assert!(part_sum == 4361, "part sum is {part_sum}");
+        assert!(gear_ratio_sum == 467835, "gear ratio is {gear_ratio_sum}");
+    }
+
+    #[test]
+    fn day3_schematic_model() {
+        let schematic = Schematic::parse(SCHEMATIC).unwrap();
+        let rules = Rules::standard();
+
+        let parts = schematic.parts(&rules);
+        assert_eq!(parts[0].number.value, 467);
+        assert_eq!(parts[0].number.span, (0, 3));
+        assert_eq!(parts[0].symbols, vec![('*', (3, 1))]);
+        assert_eq!(
+            schematic.orphans(&rules),
+            vec![
+                &Number {
+                    value: 114,
+                    row: 0,
+                    span: (5, 8)
+                },
+                &Number {
+                    value: 58,
+                    row: 5,
+                    span: (7, 9)
+                }
+            ]
+        );
+
+        let gears = schematic.gears(&rules).unwrap();
+        assert_eq!(
+            gears[0],
+            Gear {
+                position: (3, 1),
+                parts: vec![467, 35],
+                ratio: 16345
+            }
+        );
+        assert_eq!(gears.len(), 2);
+
+        // only # and $ are symbols now, and a gear is a lone $ next to one number
+        let rules = Rules {
+            symbols: Some(['#', '$'].into_iter().collect()),
+            gear: '$',
+            gear_arity: 1,
+        };
+        let parts = schematic.parts(&rules);
+        let values: Vec<u32> = parts.iter().map(|part| part.number.value).collect();
+        assert_eq!(values, vec![633, 664]);
+        assert_eq!(schematic.orphans(&rules).len(), 8);
+        let gears = schematic.gears(&rules).unwrap();
+        assert_eq!(gears.len(), 1);
+        assert_eq!(gears[0].position, (3, 8));
+        assert_eq!(gears[0].ratio, 664);
+
+        // a * that isn't a symbol can't be a gear
+        let rules = Rules {
+            symbols: Some(['#'].into_iter().collect()),
+            ..Rules::standard()
+        };
+        assert!(schematic.gears(&rules).unwrap().is_empty());
+
+        // three numbers around one gear
+        let schematic = Schematic::parse("1.2\n.*.\n..3").unwrap();
+        let rules = Rules {
+            gear_arity: 3,
+            ..Rules::standard()
+        };
+        assert_eq!(schematic.gears(&rules).unwrap()[0].ratio, 6);
+        assert!(schematic.gears(&Rules::standard()).unwrap().is_empty());
+        assert!(Schematic::parse("99999999999").is_err());
     }
 }
@@ -1,117 +1,223 @@
-use std::{cell::RefCell, collections::HashMap, fs};
+use std::{collections::HashSet, fs};
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use aoc::{overflow, render_table};
+use clap::{arg, ArgAction};
 
-const BASE: u32 = 2;
-const DEFAULT_CARD_COUNT: u32 = 1;
-const THIS_CARD_OFFSET: u32 = 1;
-const EXCLUSIVE_END_OFFSET: u32 = 1;
-
-type Range = (u32, u32);
+const BASE: u64 = 2;
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
-    let cards = fs::read_to_string(&problem.path)?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--copies "print how many copies of each card were won").action(ArgAction::SetTrue),
+        arg!(--explain "print each card's matches, points and copies").action(ArgAction::SetTrue),
+    ])?;
+    let cards = parse(&fs::read_to_string(&problem.path)?)?;
 
-    println!("sum is: {:?}", find_winning_total(&cards));
-    println!("total scratchers {:?}", find_total_card_count(&cards));
+    println!("sum is: {:?}", find_winning_total(&cards)?);
+    if matches.get_flag("copies") {
+        for (id, copies) in find_card_copies(&cards)? {
+            println!("card {id}: {copies}");
+        }
+    }
+    if matches.get_flag("explain") {
+        print!("{}", explain(&cards)?);
+    }
+    println!("total scratchers {:?}", find_total_card_count(&cards)?);
 
     Ok(())
 }
 
-fn find_total_card_count(cards: &str) -> u32 {
-    let mut card_copies: RefCell<HashMap<u32, u32>> = RefCell::new(HashMap::new());
-    cards
-        .lines()
-        .map(|card| {
-            let scratcher = card.split(":").collect::<Vec<&str>>();
-            let scratcher_id = scratcher
-                .get(0)
-                .expect("no card title found")
-                .split(' ')
-                .last()
-                .expect("no card id found")
-                .parse::<u32>()
-                .expect("failed id parse");
-
-            let scratcher_content = scratcher
-                .get(1)
-                .expect("no num lists in scratcher")
-                .split("|")
-                .collect::<Vec<&str>>();
-
-            let this_card_count = *card_copies.borrow().get(&scratcher_id).unwrap_or(&1);
-            let total_winning_nums = count_total_winning_nums(scratcher_content);
-            if total_winning_nums > 0 {
-                add_scratcher_copies(
-                    card_copies.get_mut(),
-                    // don't add yourself, and end of range is exclusive so + 1
-                    (scratcher_id + THIS_CARD_OFFSET, scratcher_id + total_winning_nums + EXCLUSIVE_END_OFFSET),
-                    this_card_count,
-                );
-            }
+fn find_total_card_count(cards: &[Scratchcard]) -> Result<u64> {
+    find_card_copies(cards)?
+        .iter()
+        .try_fold(0u64, |total, (_, copies)| total.checked_add(*copies))
+        .ok_or_else(|| overflow::<u64>("total card count".to_string()))
+}
 
-            this_card_count
-        })
-        .sum()
+// how many of each card, by id, end up held
+fn find_card_copies(cards: &[Scratchcard]) -> Result<Vec<(u32, u64)>> {
+    Ok(cards
+        .iter()
+        .map(|card| card.id)
+        .zip(copies(cards)?)
+        .collect())
 }
 
-fn add_scratcher_copies(card_copies: &mut HashMap<u32, u32>, range: Range, this_card_count: u32) {
-    for scratcher_id in (range.0)..(range.1) {
-        if card_copies.contains_key(&scratcher_id) {
-            *card_copies.get_mut(&scratcher_id).unwrap() += this_card_count;
-        } else {
-            // there is always 1 scratcher, so we process based off of this_card_count + 1
-            card_copies.insert(scratcher_id, DEFAULT_CARD_COUNT + this_card_count);
+// a card with m matches adds its copies to each of the next m cards, but
+// never past the last one. Rather than touching all m, it marks where its
+// run starts and stops, and a running total picks the marks up as it
+// passes, so O(n) overall
+fn copies(cards: &[Scratchcard]) -> Result<Vec<u64>> {
+    let mut starting = vec![0u64; cards.len() + 1];
+    let mut stopping = vec![0u64; cards.len() + 1];
+    let mut won = 0u64;
+    let mut copies = Vec::with_capacity(cards.len());
+    for (position, card) in cards.iter().enumerate() {
+        let overflowed = || overflow::<u64>(format!("copies of card {}", card.id));
+        won = won
+            .checked_add(starting[position])
+            .and_then(|won| won.checked_sub(stopping[position]))
+            .ok_or_else(overflowed)?;
+        let held = won.checked_add(1).ok_or_else(overflowed)?;
+        copies.push(held);
+
+        let last = (position + card.matches() as usize).min(cards.len() - 1);
+        if last > position {
+            starting[position + 1] = starting[position + 1]
+                .checked_add(held)
+                .ok_or_else(overflowed)?;
+            stopping[last + 1] = stopping[last + 1]
+                .checked_add(held)
+                .ok_or_else(overflowed)?;
         }
     }
+
+    Ok(copies)
 }
 
-fn find_winning_total(cards: &str) -> u32 {
-    cards
-        .lines()
-        .map(|card| {
-            let game_card_lists = card
-                .split(":")
-                .last()
-                .unwrap()
-                .split("|")
-                .collect::<Vec<&str>>();
-
-            let total_winning_nums = count_total_winning_nums(game_card_lists);
-            if total_winning_nums == 0 {
-                return 0;
-            }
+fn find_winning_total(cards: &[Scratchcard]) -> Result<u64> {
+    cards.iter().try_fold(0u64, |total, card| {
+        total
+            .checked_add(card.points()?)
+            .ok_or_else(|| overflow::<u64>(format!("winning total up to card {}", card.id)))
+    })
+}
 
-            BASE.pow(total_winning_nums as u32 - 1)
-        })
-        .sum()
+// ====================================================
+//                    Scratchcards
+// ====================================================
+
+struct Scratchcard {
+    id: u32,
+    winning: HashSet<u32>,
+    numbers: Vec<u32>,
+}
+
+impl Scratchcard {
+    // the numbers you have that win, in the order you have them
+    fn matching_numbers(&self) -> Vec<u32> {
+        self.numbers
+            .iter()
+            .filter(|num| self.winning.contains(num))
+            .copied()
+            .collect()
+    }
+
+    fn matches(&self) -> u32 {
+        self.matching_numbers().len() as u32
+    }
+
+    fn points(&self) -> Result<u64> {
+        match self.matches() {
+            0 => Ok(0),
+            matches => BASE
+                .checked_pow(matches - 1)
+                .ok_or_else(|| overflow::<u64>(format!("points of card {}", self.id))),
+        }
+    }
 }
 
-fn count_total_winning_nums(card_content: Vec<&str>) -> u32 {
-    let winning_nums = card_content.get(0).unwrap();
-    let winning_nums = to_u32_vec(&winning_nums);
+// ====================================================
+//                      Report
+// ====================================================
 
-    let card_nums = card_content.get(1).unwrap();
-    let card_nums = to_u32_vec(&card_nums);
+const REPORT_HEADER: [&str; 4] = ["card", "matching", "points", "copies"];
 
-    card_nums
+fn explain(cards: &[Scratchcard]) -> Result<String> {
+    let rows = cards
         .iter()
-        .filter(|num| winning_nums.contains(num))
-        .count() as u32
+        .zip(copies(cards)?)
+        .map(|(card, copies)| -> Result<[String; 4]> {
+            let matching = card
+                .matching_numbers()
+                .iter()
+                .map(|num| num.to_string())
+                .collect::<Vec<String>>();
+            Ok([
+                card.id.to_string(),
+                if matching.is_empty() {
+                    "-".to_string()
+                } else {
+                    matching.join(" ")
+                },
+                card.points()?.to_string(),
+                copies.to_string(),
+            ])
+        })
+        .collect::<Result<Vec<[String; 4]>>>()?;
+    Ok(render_table(REPORT_HEADER, &rows))
 }
 
-fn to_u32_vec(list: &str) -> Vec<u32> {
+// ====================================================
+//                      Parsing
+// ====================================================
+
+// every card in order. The copy rules count cards by position, so the
+// ids have to go up by one from the first
+fn parse(cards: &str) -> Result<Vec<Scratchcard>> {
+    let mut scratchcards: Vec<Scratchcard> = vec![];
+    for (index, card) in cards.lines().enumerate() {
+        let line = index + 1;
+        let scratchcard = parse_card(card).with_context(|| format!("card on line {line}"))?;
+        if let Some(previous) = scratchcards.last() {
+            if previous.id.checked_add(1) != Some(scratchcard.id) {
+                return Err(anyhow!(
+                    "card {} on line {line} doesn't follow card {}",
+                    scratchcard.id,
+                    previous.id
+                ));
+            }
+        }
+        scratchcards.push(scratchcard);
+    }
+
+    Ok(scratchcards)
+}
+
+fn parse_card(card: &str) -> Result<Scratchcard> {
+    let (title, content) = card.split_once(":").ok_or(anyhow!("no card title found"))?;
+    let id = title
+        .split(' ')
+        .next_back()
+        .ok_or(anyhow!("no card id found"))?
+        .parse::<u32>()
+        .context("card id")?;
+    let (winning, numbers) = content
+        .split_once("|")
+        .ok_or(anyhow!("no num lists in card {id}"))?;
+    let winning = to_u32_vec(winning).with_context(|| format!("winning numbers of card {id}"))?;
+    let numbers = to_u32_vec(numbers).with_context(|| format!("your numbers on card {id}"))?;
+
+    Ok(Scratchcard {
+        id,
+        winning: winning.into_iter().collect(),
+        numbers,
+    })
+}
+
+// a card never prints the same number twice on one side
+fn to_u32_vec(list: &str) -> Result<Vec<u32>> {
+    let mut seen = HashSet::new();
     list.split(' ')
-        .filter(|s| s.len() > 0)
-        .map(|s| s.parse::<u32>().unwrap())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let num = s.parse::<u32>()?;
+            if !seen.insert(num) {
+                return Err(anyhow!("{num} is listed twice"));
+            }
+            Ok(num)
+        })
         .collect()
 }
 
 #[cfg(test)]
 mod test {
+    use crate::explain;
+    use crate::find_card_copies;
     use crate::find_total_card_count;
     use crate::find_winning_total;
+    use crate::parse;
+    use crate::parse_card;
 
     #[test]
     fn day4_scratchers_example() {
@@ -122,9 +228,119 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
 ";
-        let winning_sum = find_winning_total(&s);
-        let card_cnt = find_total_card_count(&s);
+        let cards = parse(s).unwrap();
+        let winning_sum = find_winning_total(&cards).unwrap();
+        let card_cnt = find_total_card_count(&cards).unwrap();
         assert_eq!(winning_sum, 13, "total not correct: {winning_sum}");
         assert_eq!(card_cnt, 30, "card count incorrect: {card_cnt}");
     }
+
+    #[test]
+    fn day4_card_copies() {
+        let s = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+        let copies = find_card_copies(&parse(s).unwrap()).unwrap();
+        assert_eq!(
+            copies,
+            vec![(1, 1), (2, 2), (3, 4), (4, 8), (5, 14), (6, 1)]
+        );
+
+        // three matches near the end only reach the one card left
+        let s = "Card 7: 1 2 3 | 1 2 3
+Card 8: 4 | 5";
+        assert_eq!(
+            find_card_copies(&parse(s).unwrap()).unwrap(),
+            vec![(7, 1), (8, 2)]
+        );
+
+        let s = "Card 1: 1 | 1
+Card 3: 1 | 2";
+        let err = parse(s).err().unwrap();
+        assert_eq!(err.to_string(), "card 3 on line 2 doesn't follow card 1");
+        assert!(parse("Card x: 1 | 2").is_err());
+        assert_eq!(find_total_card_count(&[]).unwrap(), 0);
+
+        // each card wins a copy of every card after it, so the copies held
+        // double card by card and the 65th card would hold 2^64
+        let doubling = |cards: u32| {
+            (1..=cards)
+                .map(|id| {
+                    let numbers = (1..=cards - id)
+                        .map(|num| num.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    format!("Card {id}: {numbers} | {numbers}")
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let cards = parse(&doubling(64)).unwrap();
+        assert_eq!(find_card_copies(&cards).unwrap()[63], (64, 1 << 63));
+        assert_eq!(find_total_card_count(&cards).unwrap(), u64::MAX);
+        assert_eq!(find_winning_total(&cards).unwrap(), (1 << 63) - 1);
+        assert!(explain(&cards).is_ok());
+        // 65 matches on the first card are worth 2^64 points
+        let err = find_winning_total(&parse(&doubling(66)).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "points of card 1 overflows u64");
+        let err = find_card_copies(&parse(&doubling(65)).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "copies of card 65 overflows u64");
+    }
+
+    #[test]
+    fn day4_scratchcards() {
+        let card = parse_card("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53").unwrap();
+        assert_eq!(card.id, 1);
+        assert_eq!(card.matching_numbers(), vec![83, 86, 17, 48]);
+        assert_eq!(card.matches(), 4);
+        assert_eq!(card.points().unwrap(), 8);
+        assert_eq!(
+            parse_card("Card 2: 1 2 | 3 4").unwrap().points().unwrap(),
+            0
+        );
+
+        // duplicates would otherwise count twice
+        let err = parse_card("Card 3: 1 2 | 2 5 2").err().unwrap();
+        assert_eq!(
+            format!("{err:#}"),
+            "your numbers on card 3: 2 is listed twice"
+        );
+        let err = parse_card("Card 4: 7 7 | 7").err().unwrap();
+        assert_eq!(
+            format!("{err:#}"),
+            "winning numbers of card 4: 7 is listed twice"
+        );
+        let err = parse("Card 1: 1 | 1\nCard 2: 1 1 | 1").err().unwrap();
+        assert_eq!(
+            format!("{err:#}"),
+            "card on line 2: winning numbers of card 2: 1 is listed twice"
+        );
+        assert!(parse_card("Card 5: 1 2 3").is_err());
+    }
+
+    #[test]
+    fn day4_explain() {
+        let s = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+        assert_eq!(
+            explain(&parse(s).unwrap()).unwrap(),
+            "card  matching     points  copies
+1     83 86 17 48  8       1
+2     61 32        2       2
+3     21 1         2       4
+4     84           1       8
+5     -            0       14
+6     -            0       1
+"
+        );
+    }
 }
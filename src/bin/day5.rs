@@ -60,11 +60,11 @@ fn main() -> Result<()> {
 //                      Part 2
 // ====================================================
 
-fn map_seed_range_to_lowest_location(seed_range: Range<i64>, maps: &Vec<Vec<MapEntry>>) -> i64 {
+fn map_seed_range_to_lowest_location(seed_range: Range<i64>, maps: &[Vec<MapEntry>]) -> i64 {
     let mut mapped = vec![seed_range];
 
     // go through the maps
-    for map in &maps[..] {
+    for map in maps {
         mapped = shred(&mut mapped, map);
     }
 
@@ -80,18 +80,16 @@ fn map_seed_range_to_lowest_location(seed_range: Range<i64>, maps: &Vec<Vec<MapE
             lowest_location
         })
         .expect("there should be at least 1 item")
-        .clone()
 }
 
-fn shred(to_map: &mut Vec<Range<i64>>, map: &Vec<MapEntry>) -> Vec<Range<i64>> {
+fn shred(to_map: &mut Vec<Range<i64>>, map: &[MapEntry]) -> Vec<Range<i64>> {
     let mut mapped = vec![];
 
     // for each seed
     // find an entry with overlap
     // else, we don't map and pass it on as mapped
     // if there are any leftovers, re-shred the leftovers until we have no leftovers (recursive)
-    while to_map.len() > 0 {
-        let seed_range = to_map.pop().expect("there is a len check, haha");
+    while let Some(seed_range) = to_map.pop() {
         // find a map_entry where there is at least 1 overlapping number
         let map_entry = map
             .iter()
@@ -151,11 +149,11 @@ fn split_range(
 //                      Part 1
 // ====================================================
 
-fn map_seeds_to_location(seeds: Vec<i64>, maps: &Vec<Vec<MapEntry>>) -> Vec<i64> {
+fn map_seeds_to_location(seeds: Vec<i64>, maps: &[Vec<MapEntry>]) -> Vec<i64> {
     let mut locations: Vec<i64> = vec![];
     for seed in seeds {
         let mut mapped_seed = seed;
-        for map in &maps[..] {
+        for map in maps {
             mapped_seed = map_seed(mapped_seed, map);
         }
         locations.push(mapped_seed);
@@ -163,7 +161,7 @@ fn map_seeds_to_location(seeds: Vec<i64>, maps: &Vec<Vec<MapEntry>>) -> Vec<i64>
     locations
 }
 
-fn map_seed(seed: i64, map: &Vec<MapEntry>) -> i64 {
+fn map_seed(seed: i64, map: &[MapEntry]) -> i64 {
     for entry in map {
         if entry.src_range.contains(&seed) {
             return seed + entry.dest_offset;
@@ -178,7 +176,7 @@ fn map_seed(seed: i64, map: &Vec<MapEntry>) -> i64 {
 
 fn parse_seed_header(input: &str) -> Vec<i64> {
     let lines = input.lines().collect::<Vec<&str>>();
-    parse_seeds(lines.get(0).expect("no seeds in lines"))
+    parse_seeds(lines.first().expect("no seeds in lines"))
 }
 
 fn parse_seed_maps(input: &str) -> Vec<Vec<MapEntry>> {
@@ -186,7 +184,7 @@ fn parse_seed_maps(input: &str) -> Vec<Vec<MapEntry>> {
 
     let mut maps: Vec<Vec<MapEntry>> = vec![];
     for line in &lines[1..] {
-        if !(line.trim().len() > 0) {
+        if line.trim().is_empty() {
             continue;
         }
 
@@ -195,7 +193,7 @@ fn parse_seed_maps(input: &str) -> Vec<Vec<MapEntry>> {
             continue;
         }
         let latest_map = maps.last_mut().expect("there should be one map always");
-        latest_map.push(parse_map_entry(&line));
+        latest_map.push(parse_map_entry(line));
     }
 
     maps
@@ -218,7 +216,7 @@ fn parse_map_entry(entry: &str) -> MapEntry {
     MapEntry::new(range, dest_offset)
 }
 
-fn parse_seed_ranges(seed_ranges: &Vec<i64>) -> Vec<Range<i64>> {
+fn parse_seed_ranges(seed_ranges: &[i64]) -> Vec<Range<i64>> {
     let start = seed_ranges
         .iter()
         .enumerate()
@@ -243,12 +241,11 @@ fn parse_seeds(seeds: &str) -> Vec<i64> {
         .last()
         .expect("no seed input after ':'")
         .split(' ')
-        .filter(|seed| seed.len() > 0)
+        .filter(|seed| !seed.is_empty())
         .map(|seed| seed.trim().parse::<i64>().expect("could not parse seed"))
         .collect()
 }
 
-
 // ====================================================
 //                      Unit Tests
 // ====================================================
@@ -297,8 +294,8 @@ humidity-to-location map:
 56 93 4
 ";
 
-        let seeds = parse_seed_header(&seed_map);
-        let maps = parse_seed_maps(&seed_map);
+        let seeds = parse_seed_header(seed_map);
+        let maps = parse_seed_maps(seed_map);
 
         let nearest_seed_location = *map_seeds_to_location(seeds, &maps)
             .iter()
@@ -345,8 +342,8 @@ humidity-to-location map:
 56 93 4
 ";
 
-        let seeds = parse_seed_header(&seed_map);
-        let maps = parse_seed_maps(&seed_map);
+        let seeds = parse_seed_header(seed_map);
+        let maps = parse_seed_maps(seed_map);
 
         let mut lowest_location_of_ranges = vec![];
         let seed_ranges = parse_seed_ranges(&seeds);
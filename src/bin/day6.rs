@@ -1,162 +1,452 @@
-use anyhow::Result;
-use aoc::Part;
+use anyhow::{anyhow, Result};
+use aoc::{overflow, parse_unsigned, peak, true_range, Overflow, Part, Unsigned};
+use clap::arg;
 use std::fs;
 
-const CHARGE_SPEED: u64 = 1;
+// the puzzle's boat gains 1mm/ms of speed per ms of charging, with no top speed
+const CHARGE_SPEED: u8 = 1;
+const ACCELERATION: u32 = 1;
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--width <bits> "64 or 128 bit integers (default 64, retrying with 128 on overflow)"),
+        arg!(--"charge-speed" <speed> "speed gained per ms of charging (default 1)"),
+        arg!(--"max-speed" <speed> "top speed of the boat"),
+        arg!(--acceleration <exponent> "speed grows with charge time to this power (default 1)"),
+    ])?;
     let race_info = fs::read_to_string(problem.path)?;
+    let boat = BoatArgs {
+        charge_speed: matches.get_one::<String>("charge-speed").cloned(),
+        max_speed: matches.get_one::<String>("max-speed").cloned(),
+        acceleration: matches
+            .get_one::<String>("acceleration")
+            .map(|exponent| exponent.parse::<u32>())
+            .transpose()?,
+    };
 
-    let (times, distances);
-    if problem.part == Part::P1 {
-        (times, distances) = parse_as_multiple_races(&race_info);
-    } else {
-        (times, distances) = parse_as_one_race(&race_info);
-    }
-    let ways_to_win: u64 = times
-        .iter()
-        .zip(distances.iter())
-        .map(|(time, dist)| find_unique_charging_times(*time, *dist))
-        .product();
+    let ways_to_win = match matches.get_one::<String>("width").map(|s| &s[..]) {
+        Some("64") => solve::<u64>(&race_info, &problem.part, &boat)?.to_string(),
+        Some("128") => solve::<u128>(&race_info, &problem.part, &boat)?.to_string(),
+        Some(width) => return Err(anyhow!("unsupported width {width}, expected 64 or 128")),
+        None => match solve::<u64>(&race_info, &problem.part, &boat) {
+            Ok(ways) => ways.to_string(),
+            Err(err) if err.is::<Overflow>() => {
+                eprintln!("warning: {err}, solving with u128 instead");
+                solve::<u128>(&race_info, &problem.part, &boat)?.to_string()
+            }
+            Err(err) => return Err(err),
+        },
+    };
 
     println!("ways to win: {}", ways_to_win);
 
     Ok(())
 }
 
-// dist traveled always will peak at the middle of the charging time range
-// so let's search for the min no further than the middle
-// and the max no less than the middle as it's a bell curve,
-// and I only need to find the ends.
-fn find_unique_charging_times(time: u64, dist: u64) -> u64 {
-    find_max_charge_time(time, dist) - find_min_charge_time(time, dist) + 1
+fn solve<T: Unsigned>(race_info: &str, part: &Part, boat: &BoatArgs) -> Result<T> {
+    let boat = boat.build::<T>()?;
+    let (times, distances) = if *part == Part::P1 {
+        parse_as_multiple_races::<T>(race_info)?
+    } else {
+        parse_as_one_race::<T>(race_info)?
+    };
+
+    let mut ways_to_win = T::ONE;
+    for (time, dist) in times.iter().zip(distances.iter()) {
+        let ways = find_unique_charging_times(&boat, *time, *dist)?;
+        // the search can overflow on races the closed form still answers
+        debug_assert!(search_unique_charging_times(&boat, *time, *dist)
+            .map_or(true, |searched| searched == ways));
+        ways_to_win = ways_to_win
+            .checked_mul(ways)
+            .ok_or_else(|| overflow::<T>(format!("ways to win {ways_to_win} * {ways}")))?;
+    }
+
+    Ok(ways_to_win)
 }
 
-fn find_min_charge_time(time: u64, dist_record: u64) -> u64 {
-    let mut low = 1;
-    let mut high = time / 2; // assume it peaks in the middle
-    let mut time_charging;
-    // do a modified binary search
-    loop {
-        time_charging = (low + high) / 2;
-        if is_lower_charge_time_bound(time_charging, time, dist_record) {
-            break;
-        }
+// ====================================================
+//                      Boat
+// ====================================================
+
+// after charging, the boat holds
+// speed = charge_speed * time_charging ^ acceleration, capped at max_speed,
+// for the rest of the race. Speed only grows with charging while the time
+// left to use it only shrinks, so distance always rises and then falls
+#[derive(Debug, Clone, Copy)]
+struct Boat<T> {
+    charge_speed: T,
+    max_speed: Option<T>,
+    acceleration: u32,
+}
 
-        // check which direction to go
-        if dist_travel(time_charging, time) <= dist_record {
-            // need to go more time charging
-            low = time_charging;
-        } else {
-            // need less time charging
-            high = time_charging;
+impl<T: Unsigned> Boat<T> {
+    fn new(charge_speed: T, max_speed: Option<T>, acceleration: u32) -> Result<Self> {
+        if charge_speed == T::ZERO {
+            return Err(anyhow!("a boat that never gains speed can't race"));
+        }
+        if acceleration == 0 {
+            return Err(anyhow!(
+                "a boat needs to charge to gain speed, acceleration must be at least 1"
+            ));
         }
+
+        Ok(Self {
+            charge_speed,
+            max_speed,
+            acceleration,
+        })
     }
 
-    time_charging
-}
+    fn standard() -> Self {
+        Self::new(T::from(CHARGE_SPEED), None, ACCELERATION).expect("the standard boat is valid")
+    }
+
+    // distance is then a parabola and the winning times have a closed form
+    fn is_linear(&self) -> bool {
+        self.acceleration == 1 && self.max_speed.is_none()
+    }
 
-fn find_max_charge_time(time: u64, dist_record: u64) -> u64 {
-    let mut low = time / 2;
-    let mut high = time;
-    let mut time_charging;
+    fn speed(&self, time_charging: T) -> Result<T> {
+        let mut speed = Some(self.charge_speed);
+        for _ in 0..self.acceleration {
+            speed = speed.and_then(|speed| speed.checked_mul(time_charging));
+        }
 
-    loop {
-        time_charging = (low + high) / 2;
-        if is_upper_charge_time_bound(time_charging, time, dist_record) {
-            break;
+        match (speed, self.max_speed) {
+            (Some(speed), Some(max_speed)) => Ok(speed.min(max_speed)),
+            // too fast to represent is over any top speed
+            (None, Some(max_speed)) => Ok(max_speed),
+            (Some(speed), None) => Ok(speed),
+            (None, None) => Err(overflow::<T>(format!(
+                "speed after {time_charging}ms of charging"
+            ))),
         }
-        // check which direction to go
-        if dist_travel(time_charging, time) <= dist_record {
-            // need to go with less time charging
-            high = time_charging;
-        } else {
-            // need more time charging
-            low = time_charging;
+    }
+
+    fn dist_travel(&self, time_charging: T, time: T) -> Result<T> {
+        // no time left to move, however fast the boat would go
+        if time_charging == time {
+            return Ok(T::ZERO);
         }
+
+        self.speed(time_charging)?
+            .checked_mul(time - time_charging)
+            .ok_or_else(|| {
+                overflow::<T>(format!(
+                    "distance after {time_charging}ms of charging in a {time}ms race"
+                ))
+            })
     }
 
-    time_charging
+    fn beats_record(&self, time_charging: T, time: T, dist_record: T) -> Result<bool> {
+        Ok(self.dist_travel(time_charging, time)? > dist_record)
+    }
 }
 
-fn is_lower_charge_time_bound(time_charging: u64, time: u64, dist_record: u64) -> bool {
-    dist_travel(time_charging - 1, time) <= dist_record
-        && dist_travel(time_charging, time) > dist_record
+// boat settings from the command line, parsed once the integer width is known
+#[derive(Debug, Default)]
+struct BoatArgs {
+    charge_speed: Option<String>,
+    max_speed: Option<String>,
+    acceleration: Option<u32>,
 }
 
-fn is_upper_charge_time_bound(time_charging: u64, time: u64, dist_record: u64) -> bool {
-    dist_travel(time_charging, time) > dist_record
-        && dist_travel(time_charging + 1, time) <= dist_record
+impl BoatArgs {
+    fn build<T: Unsigned>(&self) -> Result<Boat<T>> {
+        let standard = Boat::<T>::standard();
+        let charge_speed = match &self.charge_speed {
+            Some(speed) => parse_unsigned(speed)?,
+            None => standard.charge_speed,
+        };
+        let max_speed = self.max_speed.as_deref().map(parse_unsigned).transpose()?;
+        Boat::new(
+            charge_speed,
+            max_speed,
+            self.acceleration.unwrap_or(standard.acceleration),
+        )
+    }
 }
 
-fn dist_travel(time_charging: u64, time: u64) -> u64 {
-    time_charging * CHARGE_SPEED * (time - time_charging)
+// ====================================================
+//                      Solving
+// ====================================================
+
+fn find_unique_charging_times<T: Unsigned>(boat: &Boat<T>, time: T, dist_record: T) -> Result<T> {
+    if boat.is_linear() {
+        closed_form_charging_times(boat, time, dist_record)
+    } else {
+        search_unique_charging_times(boat, time, dist_record)
+    }
+}
+
+// dist_travel(t) > dist_record is t^2 - time*t + dist_record / charge_speed < 0
+// (an integer distance beats the record iff it beats the record rounded down
+// to the charge speed), which holds strictly between the roots
+// (time +- sqrt(time^2 - 4 * record)) / 2, so the integer square root gets
+// the lower bound to within a step and the curve is symmetric around time / 2
+fn closed_form_charging_times<T: Unsigned>(boat: &Boat<T>, time: T, dist_record: T) -> Result<T> {
+    debug_assert!(boat.is_linear());
+    let record = dist_record / boat.charge_speed;
+    let time_squared = time
+        .checked_mul(time)
+        .ok_or_else(|| overflow::<T>(format!("race of {time}ms squared")))?;
+    // no real roots, the record can't be beaten
+    let discriminant = match record
+        .checked_mul(T::from(4))
+        .and_then(|record| time_squared.checked_sub(record))
+    {
+        Some(discriminant) => discriminant,
+        None => return Ok(T::ZERO),
+    };
+
+    let two = T::from(2);
+    let peak = time / two;
+    let mut min_charge_time = (time - discriminant.isqrt()) / two;
+    while min_charge_time <= peak && !boat.beats_record(min_charge_time, time, dist_record)? {
+        min_charge_time = min_charge_time + T::ONE;
+    }
+    while min_charge_time > T::ZERO
+        && boat.beats_record(min_charge_time - T::ONE, time, dist_record)?
+    {
+        min_charge_time = min_charge_time - T::ONE;
+    }
+    if min_charge_time > peak {
+        return Ok(T::ZERO);
+    }
+
+    let max_charge_time = time - min_charge_time;
+    Ok(max_charge_time - min_charge_time + T::ONE)
+}
+
+// dist traveled always peaks somewhere in the charging time range
+// and it's a bell curve, so find the top, then binary search either side
+// of it and I only need to find the ends. Works for any boat, and is the
+// cross-check for the closed form
+fn search_unique_charging_times<T: Unsigned>(boat: &Boat<T>, time: T, dist: T) -> Result<T> {
+    // distances too big to represent sort above every other distance
+    let best = peak(T::ZERO, time, |time_charging| {
+        boat.dist_travel(time_charging, time)
+            .map_or((true, T::ZERO), |dist| (false, dist))
+    })
+    .expect("a race always has a charge time");
+    // every other distance (and speed) is at most the longest one,
+    // so if that one fits they all do
+    boat.dist_travel(best, time)?;
+
+    Ok(true_range(T::ZERO, time, best, |time_charging| {
+        boat.beats_record(time_charging, time, dist)
+            .expect("no distance is longer than the peak")
+    })
+    .map_or(T::ZERO, |(min_charge_time, max_charge_time)| {
+        max_charge_time - min_charge_time + T::ONE
+    }))
 }
 
 // ====================================================
 //                      Parsing
 // ====================================================
 
-fn parse_as_multiple_races(input: &str) -> (Vec<u64>, Vec<u64>) {
+fn parse_as_multiple_races<T: Unsigned>(input: &str) -> Result<(Vec<T>, Vec<T>)> {
     let input = input.lines().collect::<Vec<&str>>();
     let times = input
-        .get(0)
-        .expect("no times with title found")
+        .first()
+        .ok_or(anyhow!("no times with title found"))?
         .split(":")
         .last()
-        .expect("no times found");
+        .ok_or(anyhow!("no times found"))?;
     let distances = input
         .get(1)
-        .expect("no dist with title found")
+        .ok_or(anyhow!("no dist with title found"))?
         .split(":")
         .last()
-        .expect("no dist found");
-    (
+        .ok_or(anyhow!("no dist found"))?;
+    Ok((
         times
             .split_whitespace()
-            .map(|num| num.parse::<u64>().expect("failed to parse time"))
-            .collect(),
+            .map(parse_unsigned)
+            .collect::<Result<Vec<T>>>()?,
         distances
             .split_whitespace()
-            .map(|num| num.parse::<u64>().expect("failed to parse time"))
-            .collect(),
-    )
+            .map(parse_unsigned)
+            .collect::<Result<Vec<T>>>()?,
+    ))
 }
 
-fn parse_as_one_race(input: &str) -> (Vec<u64>, Vec<u64>) {
+// the kerning is bad, so the digits of every column are one big race
+fn parse_as_one_race<T: Unsigned>(input: &str) -> Result<(Vec<T>, Vec<T>)> {
     let input = input.lines().collect::<Vec<&str>>();
     let time = input
-        .get(0)
-        .expect("no times with title")
+        .first()
+        .ok_or(anyhow!("no times with title"))?
         .split(":")
         .last()
         .map(|time| time.replace(" ", ""))
-        .expect("no time found");
+        .ok_or(anyhow!("no time found"))?;
 
     let dist = input
         .get(1)
-        .expect("no times with title")
+        .ok_or(anyhow!("no dist with title"))?
         .split(":")
         .last()
         .map(|dist| dist.replace(" ", ""))
-        .expect("no time found");
+        .ok_or(anyhow!("no dist found"))?;
 
-    (
-        vec![time.parse::<u64>().expect("failed to parse time")],
-        vec![dist.parse::<u64>().expect("failed to parse dist")],
-    )
+    Ok((vec![parse_unsigned(&time)?], vec![parse_unsigned(&dist)?]))
 }
 
 #[cfg(test)]
 mod test {
-    use crate::find_unique_charging_times;
+    use crate::{find_unique_charging_times, search_unique_charging_times, solve, Boat, BoatArgs};
+    use aoc::{Overflow, Part};
 
     #[test]
     fn simple_input() {
-        assert_eq!(4, find_unique_charging_times(7, 9));
-        assert_eq!(8, find_unique_charging_times(15, 40));
-        assert_eq!(9, find_unique_charging_times(30, 200));
-        assert_eq!(71503, find_unique_charging_times(71530, 940200));
+        let boat = Boat::standard();
+        assert_eq!(4, find_unique_charging_times(&boat, 7u64, 9).unwrap());
+        assert_eq!(8, find_unique_charging_times(&boat, 15, 40).unwrap());
+        assert_eq!(9, find_unique_charging_times(&boat, 30, 200).unwrap());
+        assert_eq!(
+            71503,
+            find_unique_charging_times(&boat, 71530, 940200).unwrap()
+        );
+    }
+
+    #[test]
+    fn day6_edge_races() {
+        let boat = Boat::standard();
+        // the best you can do is 3 * 3 = 9, which only ties
+        assert_eq!(0, find_unique_charging_times(&boat, 6u32, 9).unwrap());
+        assert_eq!(0, find_unique_charging_times(&boat, 0u32, 0).unwrap());
+        // only charging for exactly half the race wins
+        assert_eq!(1, find_unique_charging_times(&boat, 6u32, 8).unwrap());
+        // any time other than 0 or the full race wins
+        assert_eq!(5, find_unique_charging_times(&boat, 6u32, 0).unwrap());
+
+        let boat = Boat::standard();
+        assert_eq!(0, find_unique_charging_times(&boat, 7, u128::MAX).unwrap());
+        let time = u64::MAX as u128;
+        assert_eq!(
+            time - 1,
+            find_unique_charging_times(&boat, time, 0).unwrap()
+        );
+        let peak = (time / 2) * (time - time / 2);
+        assert_eq!(
+            2,
+            find_unique_charging_times(&boat, time, peak - 1).unwrap()
+        );
+        assert_eq!(0, find_unique_charging_times(&boat, time, peak).unwrap());
+        assert!(find_unique_charging_times(&boat, u128::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn day6_closed_form_matches_search() {
+        for charge_speed in 1u32..4 {
+            let boat = Boat::new(charge_speed, None, 1).unwrap();
+            for time in 0u32..120 {
+                for dist in 0..(charge_speed * time * time / 4 + 2) {
+                    assert_eq!(
+                        find_unique_charging_times(&boat, time, dist).unwrap(),
+                        search_unique_charging_times(&boat, time, dist).unwrap(),
+                        "race {time}ms, record {dist}mm, {boat:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn day6_other_boats() {
+        // top speed of 3: distances 6 10 12 9 6 3 for 1..=6ms of charging
+        let capped = Boat::new(1u64, Some(3), 1).unwrap();
+        assert_eq!(2, find_unique_charging_times(&capped, 7, 9).unwrap());
+        // speed is the square of the charge time: 6 20 36 48 50 36
+        let quadratic = Boat::new(1u64, None, 2).unwrap();
+        assert_eq!(5, find_unique_charging_times(&quadratic, 7, 9).unwrap());
+        assert!(Boat::new(0u64, None, 1).is_err());
+        assert!(Boat::new(1u64, None, 0).is_err());
+
+        // count every charge time by hand
+        for (charge_speed, max_speed, acceleration) in
+            [(2, Some(5), 1), (1, None, 3), (3, Some(40), 2)]
+        {
+            let boat = Boat::new(charge_speed, max_speed, acceleration).unwrap();
+            for time in 0u64..40 {
+                for dist in (0..400).step_by(7) {
+                    let expected =
+                        brute_force(charge_speed, max_speed, acceleration, time, dist) as u64;
+                    assert_eq!(
+                        expected,
+                        find_unique_charging_times(&boat, time, dist).unwrap(),
+                        "race {time}ms, record {dist}mm, {boat:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    // counts winning charge times straight from the formula, in u128 so
+    // nothing a u64 boat can race overflows
+    fn brute_force(
+        charge_speed: u64,
+        max_speed: Option<u64>,
+        acceleration: u32,
+        time: u64,
+        dist: u64,
+    ) -> u128 {
+        (0..=time as u128)
+            .filter(|charging| {
+                let speed = charge_speed as u128 * charging.pow(acceleration);
+                let speed = max_speed.map_or(speed, |max_speed| speed.min(max_speed as u128));
+                speed * (time as u128 - charging) > dist as u128
+            })
+            .count() as u128
+    }
+
+    #[test]
+    fn day6_fast_boats_overflow() {
+        // charging the whole race goes nowhere, however fast the boat would be
+        let boat = Boat::new(1u64, None, 3).unwrap();
+        assert_eq!(boat.dist_travel(3_000_000, 3_000_000).unwrap(), 0);
+
+        // (3 million ms)^3 is too fast for u64, but not for u128
+        let record = 1_000_000_000_000_000_000;
+        let err = find_unique_charging_times(&boat, 3_000_000, record).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        let boat = Boat::new(1u128, None, 3).unwrap();
+        let expected = brute_force(1, None, 3, 3_000_000, record);
+        assert_eq!(expected, 2_993_061);
+        assert_eq!(
+            expected,
+            find_unique_charging_times(&boat, 3_000_000, record as u128).unwrap()
+        );
+
+        // a top speed keeps the boat in range however long it charges
+        let capped = Boat::new(1u64, Some(10), 3).unwrap();
+        assert_eq!(
+            find_unique_charging_times(&capped, 3_000_000, record).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn day6_overflow_is_reported() {
+        let boat = BoatArgs::default();
+        let races = "Time:      7  15   30
+Distance:  9  40  200";
+        assert_eq!(solve::<u64>(races, &Part::P2, &boat).unwrap(), 71503);
+
+        // the record concatenates to more than u64 can hold
+        let races = "Time:      7  15   30   4000000000
+Distance:  9  40  200  4000000000000000000";
+        let err = solve::<u64>(races, &Part::P2, &boat).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert_eq!(
+            solve::<u128>(races, &Part::P2, &boat).unwrap(),
+            715_277_711_382_089
+        );
+
+        let err = solve::<u8>("Time: 200\nDistance: 1", &Part::P1, &boat).unwrap_err();
+        assert_eq!(err.to_string(), "race of 200ms squared overflows u8");
     }
 }
@@ -1,234 +1,573 @@
-use std::{cmp::Ordering, collections::HashMap, fs};
+use std::{collections::HashMap, fs};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use aoc::Part;
+use aoc::{render_table, Part};
+use clap::{arg, ArgAction};
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--cards <order> "card symbols from weakest to strongest, e.g. 23456789TJQKA"),
+        arg!(--wild <cards> "card symbols that stand in for any other card"),
+        arg!(--hands <order> "comma separated hand types from weakest to strongest"),
+        arg!(--straights "score five consecutive cards as a straight, ranked as in poker")
+            .action(ArgAction::SetTrue),
+        arg!(--duplicates <policy> "warn (default) or error when the same hand is dealt twice"),
+        arg!(--explain "list every hand with its type, rank and winnings")
+            .action(ArgAction::SetTrue),
+        arg!(--csv "print the --explain report as csv").action(ArgAction::SetTrue),
+    ])?;
     let input = fs::read_to_string(problem.path)?;
-    let mut hands = parse(&input, problem.part == Part::P2);
-    hands.sort_by(|a, z| {
-        a.compare(z)
-    });
 
-    // weakest hand is the lowest rank
-    let total_winnings: u64 = hands.iter().enumerate().map(|(index, play)| {
-        play.bid * ((index + 1) as u64)
-    }).sum();
+    let mut rules = if problem.part == Part::P2 {
+        Rules::camel_jokers()
+    } else {
+        Rules::camel()
+    };
+    if let Some(cards) = matches.get_one::<String>("cards") {
+        rules = rules.with_card_order(cards)?;
+    }
+    if let Some(wild) = matches.get_one::<String>("wild") {
+        rules = rules.with_wild(wild)?;
+    }
+    if let Some(hands) = matches.get_one::<String>("hands") {
+        let hands = hands
+            .split(',')
+            .map(Hand::new)
+            .collect::<Result<Vec<Hand>>>()?;
+        rules = rules.with_hand_order(hands)?;
+    }
+    if matches.get_flag("straights") {
+        rules = rules.with_straights()?;
+    }
 
-    println!("total winnings is {total_winnings}");
+    let mut hands = parse(&input, &rules)?;
+    let duplicates = find_duplicates(&hands);
+    let strict = match matches.get_one::<String>("duplicates").map(|s| &s[..]) {
+        None | Some("warn") => false,
+        Some("error") => true,
+        Some(policy) => return Err(anyhow!("unknown duplicates policy {policy}")),
+    };
+    for (first, duplicate) in &duplicates {
+        if strict {
+            return Err(anyhow!("hand on line {duplicate} duplicates line {first}"));
+        }
+        eprintln!(
+            "warning: hand on line {duplicate} duplicates line {first}, ranking by input order"
+        );
+    }
+    rank(&mut hands);
+
+    if matches.get_flag("csv") {
+        print!("{}", explain_csv(&hands));
+    } else if matches.get_flag("explain") {
+        print!("{}", explain(&hands));
+    }
+
+    // weakest hand is the lowest rank
+    let total_winnings: u64 = hands
+        .iter()
+        .enumerate()
+        .map(|(index, play)| play.bid * ((index + 1) as u64))
+        .sum();
+
+    // keep stdout pure csv so it can be piped straight into other tools
+    if matches.get_flag("csv") {
+        eprintln!("total winnings is {total_winnings}");
+    } else {
+        println!("total winnings is {total_winnings}");
+    }
     Ok(())
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+// declared in face value order, how strong a card is comes from the Rules
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 enum Card {
-    Ace,
-    King,
-    Queen,
-    Jack,
-    Ten,
-    Nine,
-    Eight,
-    Seven,
-    Six,
-    Five,
-    Four,
-    Three,
     Two,
-    Joker,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+const CARD_KINDS: usize = Card::Ace as usize + 1;
+const ALL_CARDS: [Card; CARD_KINDS] = [
+    Card::Two,
+    Card::Three,
+    Card::Four,
+    Card::Five,
+    Card::Six,
+    Card::Seven,
+    Card::Eight,
+    Card::Nine,
+    Card::Ten,
+    Card::Jack,
+    Card::Queen,
+    Card::King,
+    Card::Ace,
+];
+const CARD_BITS: u32 = 4;
+const HAND_SIZE: usize = 5;
+
+// the hand types camel cards scores, weakest to strongest
+const CAMEL_HANDS: [Hand; 7] = [
+    Hand::HighCard,
+    Hand::OnePairs,
+    Hand::TwoPairs,
+    Hand::ThreeKind,
+    Hand::FullHouse,
+    Hand::FourKind,
+    Hand::FiveKind,
+];
+
+#[derive(Debug, Clone)]
+struct Rules {
+    // indexed by card, used to break ties card by card
+    card_strength: [u32; CARD_KINDS],
+    wild: Vec<Card>,
+    // weakest to strongest, a hand type left out is never scored
+    hand_order: Vec<Hand>,
 }
 
-impl Card {
-    fn value(&self) -> u32 {
-        match self {
-            Card::Ace => 14,
-            Card::King => 13,
-            Card::Queen => 12,
-            Card::Jack => 11,
-            Card::Ten => 10,
-            Card::Nine => 9,
-            Card::Eight => 8,
-            Card::Seven => 7,
-            Card::Six => 6,
-            Card::Five => 5,
-            Card::Four => 4,
-            Card::Three => 3,
-            Card::Two => 2,
-            Card::Joker => 1,
+impl Rules {
+    fn new(card_order: &str, wild: &str, hand_order: Vec<Hand>) -> Result<Self> {
+        let card_order = to_cards(card_order)?;
+        let mut card_strength = [None; CARD_KINDS];
+        for (strength, card) in card_order.iter().enumerate() {
+            if card_strength[*card as usize].is_some() {
+                return Err(anyhow!("card {card:?} is ranked more than once"));
+            }
+            card_strength[*card as usize] = Some(strength as u32);
+        }
+        let card_strength = card_strength
+            .into_iter()
+            .enumerate()
+            .map(|(card, strength)| {
+                strength.ok_or(anyhow!(
+                    "card {} is missing a rank",
+                    to_symbol(ALL_CARDS[card])
+                ))
+            })
+            .collect::<Result<Vec<u32>>>()?
+            .try_into()
+            .expect("one strength per card");
+
+        for hand in CAMEL_HANDS {
+            if !hand_order.contains(&hand) {
+                return Err(anyhow!("hand ranking is missing {hand:?}"));
+            }
+        }
+        if hand_order.contains(&Hand::Empty) {
+            return Err(anyhow!("an empty hand can't be ranked"));
+        }
+
+        Ok(Self {
+            card_strength,
+            wild: to_cards(wild)?,
+            hand_order,
+        })
+    }
+
+    fn camel() -> Self {
+        Self::new("23456789TJQKA", "", CAMEL_HANDS.to_vec()).expect("camel rules are valid")
+    }
+
+    // J cards are jokers: wild, but the weakest card when breaking ties
+    fn camel_jokers() -> Self {
+        Self::new("J23456789TQKA", "J", CAMEL_HANDS.to_vec()).expect("joker rules are valid")
+    }
+
+    fn with_card_order(self, card_order: &str) -> Result<Self> {
+        let wild = self
+            .wild
+            .iter()
+            .map(|card| to_symbol(*card))
+            .collect::<String>();
+        Self::new(card_order, &wild, self.hand_order)
+    }
+
+    fn with_wild(self, wild: &str) -> Result<Self> {
+        Ok(Self {
+            wild: to_cards(wild)?,
+            ..self
+        })
+    }
+
+    fn with_hand_order(self, hand_order: Vec<Hand>) -> Result<Self> {
+        let card_order = self.card_order();
+        let wild = self
+            .wild
+            .iter()
+            .map(|card| to_symbol(*card))
+            .collect::<String>();
+        Self::new(&card_order, &wild, hand_order)
+    }
+
+    // poker puts a straight between three of a kind and a full house
+    fn with_straights(mut self) -> Result<Self> {
+        if self.hand_order.contains(&Hand::Straight) {
+            return Ok(self);
         }
+        let full_house = self
+            .hand_order
+            .iter()
+            .position(|hand| *hand == Hand::FullHouse)
+            .ok_or(anyhow!("hand ranking is missing FullHouse"))?;
+        self.hand_order.insert(full_house, Hand::Straight);
+        Ok(self)
+    }
+
+    fn card_order(&self) -> String {
+        let mut cards = ALL_CARDS.to_vec();
+        cards.sort_by_key(|card| self.card_strength(*card));
+        cards.into_iter().map(to_symbol).collect()
+    }
+
+    fn card_strength(&self, card: Card) -> u32 {
+        self.card_strength[card as usize]
+    }
+
+    // Empty and any hand type left out of the ranking score lowest
+    fn hand_strength(&self, hand: Hand) -> u32 {
+        self.hand_order
+            .iter()
+            .position(|ranked| *ranked == hand)
+            .map_or(0, |position| position as u32 + 1)
+    }
+
+    fn is_wild(&self, card: Card) -> bool {
+        self.wild.contains(&card)
+    }
+
+    // the hand the cards make at face value, wild cards counted as themselves
+    fn face_value_hand(&self, cards: &[Card]) -> Hand {
+        let hand = determine_hand(&count_signature(cards));
+        if self.hand_strength(Hand::Straight) > self.hand_strength(hand)
+            && can_straight(cards, cards.len())
+        {
+            return Hand::Straight;
+        }
+
+        hand
+    }
+
+    // the strongest hand the cards can make once every wild card has
+    // been turned into whichever card helps the most
+    fn hand(&self, cards: &[Card]) -> Hand {
+        let fixed = cards
+            .iter()
+            .filter(|card| !self.is_wild(**card))
+            .copied()
+            .collect::<Vec<Card>>();
+        let wilds = cards.len() - fixed.len();
+
+        let mut candidates = wild_signatures(&count_signature(&fixed), wilds)
+            .iter()
+            .map(|signature| determine_hand(signature))
+            .collect::<Vec<Hand>>();
+        if self.hand_order.contains(&Hand::Straight) && can_straight(&fixed, cards.len()) {
+            candidates.push(Hand::Straight);
+        }
+
+        candidates
+            .into_iter()
+            .max_by_key(|hand| self.hand_strength(*hand))
+            .unwrap_or(Hand::Empty)
     }
 }
 
 #[derive(Debug)]
 struct Play {
     cards: Vec<Card>,
-    card_count: HashMap<Card, u32>,
+    hand: Hand,
+    // what the hand would be if the wild cards weren't wild
+    face_value: Hand,
     bid: u64,
+    // hand strength in the high bits, then each card's strength in order, so
+    // sorting by key is the same as comparing hands and then breaking ties card by card
+    key: u32,
+    // 1-based line in the input, breaks ties between identical hands
+    line: usize,
 }
 
 impl Play {
-    fn compare(&self, other: &Self) -> Ordering {
-        // if both HighCard, we find which has the highest card value
-        let a = self.hand();
-        let b = other.hand();
-
-        return if a.value() > b.value() {
-            Ordering::Greater
-        } else if a.value() < b.value() {
-            Ordering::Less
-        } else {
-            // they are the same value, so find high card
-            for (a, b) in self.cards.iter().zip(other.cards.iter()) {
-                if a.value() > b.value() {
-                    return Ordering::Greater;
-                } else if a.value() < b.value() {
-                    return Ordering::Less;
-                }
-            }
-            // this should never happen...
-            println!("equal hands (weird...)");
-            Ordering::Equal
-        };
-    }
-
-    fn hand(&self) -> Hand {
-        let original = determine_hand(&self.card_count);
-        // determine hand without jokers, then do the joker upgrades
-        // then compare with previous non wilded hand and see what is
-        // the best hand
-        let mut hand_upgrades = self.jokers();
-        if hand_upgrades == 5 {
-            return Hand::FiveKind;
-        }
-        let no_jokers = self.card_count.clone()
-            .iter()
-            .filter(|(card, _)| **card != Card::Joker)
-            .map(|(card, count)| (card.clone(), *count))
-            .collect::<HashMap<Card, u32>>();
-        let mut hand = determine_hand(&no_jokers);
-        while hand_upgrades > 0 {
-            if hand == Hand::FourKind {
-                hand = Hand::FiveKind;
-            } else if hand == Hand::FullHouse || hand == Hand::ThreeKind {
-                hand = Hand::FourKind;
-            } else if hand == Hand::TwoPairs {
-                hand = Hand::FullHouse;
-            } else if hand == Hand::OnePairs {
-                hand = Hand::ThreeKind;
-            } else if hand == Hand::HighCard {
-                hand = Hand::OnePairs;
-            }
-            hand_upgrades -= 1;
-        }
+    fn new(cards: &[Card], bid: u64, line: usize, rules: &Rules) -> Self {
+        let hand = rules.hand(cards);
+        let key = cards.iter().fold(rules.hand_strength(hand), |key, card| {
+            (key << CARD_BITS) | rules.card_strength(*card)
+        });
 
-        if original.value() > hand.value() { original } else { hand }
+        Self {
+            face_value: rules.face_value_hand(cards),
+            cards: cards.to_vec(),
+            hand,
+            bid,
+            key,
+            line,
+        }
     }
+}
+
+// weakest first; identical hands keep their input order so winnings
+// don't depend on how the sort treats equal keys
+fn rank(hands: &mut [Play]) {
+    hands.sort_unstable_by_key(|play| (play.key, play.line));
+}
 
-    fn jokers(&self) -> u32 {
-        *self.card_count.get(&Card::Joker).unwrap_or(&0)
+// how many of each card are in the hand, largest group first,
+// e.g. 23332 is [3, 2] and KTJJT is [2, 2, 1]
+fn count_signature(cards: &[Card]) -> Vec<u8> {
+    let mut counts = [0u8; CARD_KINDS];
+    for card in cards {
+        counts[*card as usize] += 1;
     }
+
+    let mut signature = counts
+        .into_iter()
+        .filter(|count| *count > 0)
+        .collect::<Vec<u8>>();
+    signature.sort_unstable_by(|a, b| b.cmp(a));
+    signature
 }
 
-fn determine_hand(card_count: &HashMap<Card, u32>) -> Hand {
-    // let's use both card count and num unique cards to determine the hand
-    let most_cards = card_count.iter().map(|(_, count)| *count).max().unwrap_or(0);
-    return match most_cards {
-        5 => Hand::FiveKind,
-        4 => Hand::FourKind,
-        3 => {
-            // also do another code path here for FullHouse
-            // Full House means there will ONLY be 2 unique cards
-            if card_count.len() == 2 {
-                return Hand::FullHouse;
-            }
-            return Hand::ThreeKind;
-        }
-        2 => {
-            // determine if it's two pair or one pair
-            if card_count.iter().map(|(_, count)| *count).filter(|count| *count == 2).collect::<Vec<u32>>().len() == 2 {
-                return Hand::TwoPairs;
-            }
-            return Hand::OnePairs;
+// every signature reachable by adding each wild card to one of the
+// groups, or starting a group of its own
+fn wild_signatures(signature: &[u8], wilds: usize) -> Vec<Vec<u8>> {
+    if wilds == 0 {
+        return vec![signature.to_vec()];
+    }
+
+    let mut signatures = vec![];
+    for group in 0..=signature.len() {
+        let mut next = signature.to_vec();
+        if group == signature.len() {
+            next.push(1);
+        } else {
+            next[group] += 1;
         }
-        1 => Hand::HighCard,
-        0 => Hand::Empty,
-        _ => panic!("there must be between 1-5 cards in card_count")
+        next.sort_unstable_by(|a, b| b.cmp(a));
+        signatures.extend(wild_signatures(&next, wilds - 1));
+    }
+
+    signatures
+}
+
+// a straight is a full hand of distinct cards in a run of face values,
+// wild cards fill whatever gaps the fixed cards leave. As in poker an ace
+// plays either high or low, so A2345 is a straight but QKA23 isn't
+fn can_straight(fixed: &[Card], hand_size: usize) -> bool {
+    if hand_size != HAND_SIZE || count_signature(fixed).iter().any(|count| *count > 1) {
+        return false;
+    }
+
+    let ace_high = |card: &Card| *card as usize;
+    let ace_low = |card: &Card| match card {
+        Card::Ace => 0,
+        _ => *card as usize + 1,
+    };
+    let in_run = |value: &dyn Fn(&Card) -> usize| match (
+        fixed.iter().map(value).min(),
+        fixed.iter().map(value).max(),
+    ) {
+        (Some(low), Some(high)) => high - low < HAND_SIZE,
+        _ => true,
     };
+
+    in_run(&ace_high) || in_run(&ace_low)
 }
 
+fn determine_hand(signature: &[u8]) -> Hand {
+    match signature {
+        [5, ..] => Hand::FiveKind,
+        [4, ..] => Hand::FourKind,
+        // Full House means there will ONLY be 2 unique cards
+        [3, 2] => Hand::FullHouse,
+        [3, ..] => Hand::ThreeKind,
+        [2, 2, ..] => Hand::TwoPairs,
+        [2, ..] => Hand::OnePairs,
+        [1, ..] => Hand::HighCard,
+        [] => Hand::Empty,
+        _ => panic!("there must be between 1-5 cards in a hand"),
+    }
+}
 
-#[derive(PartialEq, Eq, Debug)]
+// declared weakest to strongest so the derived Ord ranks the hands
+// the way poker does, the Rules decide how they are scored
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 enum Hand {
-    FiveKind,
-    FourKind,
-    FullHouse,
+    Empty,
+    HighCard,
+    OnePairs,
+    TwoPairs,
     // e.g. 23332
     ThreeKind,
-    TwoPairs,
-    OnePairs,
-    HighCard,
-    Empty,
+    // e.g. 6789T
+    Straight,
+    FullHouse,
+    FourKind,
+    FiveKind,
 }
 
 impl Hand {
-    fn value(&self) -> u32 {
-        match self {
-            Hand::FiveKind => 7,
-            Hand::FourKind => 6,
-            Hand::FullHouse => 5,
-            Hand::ThreeKind => 4,
-            Hand::TwoPairs => 3,
-            Hand::OnePairs => 2,
-            Hand::HighCard => 1,
-            Hand::Empty => 0,
+    fn new(name: &str) -> Result<Self> {
+        match name.trim() {
+            "high" => Ok(Hand::HighCard),
+            "pair" => Ok(Hand::OnePairs),
+            "two-pair" => Ok(Hand::TwoPairs),
+            "three" => Ok(Hand::ThreeKind),
+            "straight" => Ok(Hand::Straight),
+            "full-house" => Ok(Hand::FullHouse),
+            "four" => Ok(Hand::FourKind),
+            "five" => Ok(Hand::FiveKind),
+            _ => Err(anyhow!("unknown hand type {name}")),
         }
     }
 }
 
 // ====================================================
-//                      Parsing
+//                      Reporting
 // ====================================================
-fn parse(input: &str, jack_as_joker: bool) -> Vec<Play> {
-    input.lines().map(|line| line_to_hand(line, jack_as_joker)).collect()
+const REPORT_HEADER: [&str; 7] = [
+    "rank",
+    "line",
+    "cards",
+    "hand",
+    "face value",
+    "bid",
+    "winnings",
+];
+
+// one row per hand, expects hands already ranked weakest first
+fn report_rows(hands: &[Play]) -> Vec<[String; 7]> {
+    hands
+        .iter()
+        .enumerate()
+        .map(|(index, play)| {
+            let rank = index as u64 + 1;
+            [
+                rank.to_string(),
+                play.line.to_string(),
+                play.cards.iter().map(|card| to_symbol(*card)).collect(),
+                format!("{:?}", play.hand),
+                format!("{:?}", play.face_value),
+                play.bid.to_string(),
+                (play.bid * rank).to_string(),
+            ]
+        })
+        .collect()
+}
+
+fn explain(hands: &[Play]) -> String {
+    render_table(REPORT_HEADER, &report_rows(hands))
 }
 
+fn explain_csv(hands: &[Play]) -> String {
+    let mut out = REPORT_HEADER.join(",");
+    out.push('\n');
+    for row in report_rows(hands) {
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
 
-fn line_to_hand(line: &str, jack_as_joker: bool) -> Play {
-    let play = line.split_whitespace().collect::<Vec<&str>>();
-    let cards = play.get(0).expect("hand does not exist after split");
-    let cards = cards.chars().map(|ch| to_card(ch, jack_as_joker)).collect::<Vec<Card>>();
-    let bid = play.get(1).expect("bid does not exist after split");
-    let bid = bid.parse::<u64>().expect("bid failed to parse");
+// ====================================================
+//                      Parsing
+// ====================================================
+fn parse(input: &str, rules: &Rules) -> Result<Vec<Play>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| line_to_hand(line, index + 1, rules))
+        .collect()
+}
 
-    let mut card_count = HashMap::new();
-    for card in &cards {
-        if let Some(count) = card_count.get(card) {
-            card_count.insert(card.clone(), count + 1);
+// pairs of (first line, repeated line) for every hand dealt more than once
+fn find_duplicates(hands: &[Play]) -> Vec<(usize, usize)> {
+    let mut first_seen: HashMap<u32, usize> = HashMap::new();
+    let mut duplicates = vec![];
+    for play in hands {
+        if let Some(first) = first_seen.get(&play.key) {
+            duplicates.push((*first, play.line));
         } else {
-            card_count.insert(card.clone(), 1);
+            first_seen.insert(play.key, play.line);
+        }
+    }
+
+    duplicates
+}
+
+// the sort key packs exactly HAND_SIZE cards, so any other count is an error
+fn line_to_hand(line: &str, number: usize, rules: &Rules) -> Result<Play> {
+    let (cards, bid) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+        [cards, bid] => (cards, bid),
+        _ => {
+            return Err(anyhow!(
+                "expected \"cards bid\" on line {number}, found {line:?}"
+            ))
         }
+    };
+    let cards = to_cards(cards).map_err(|err| anyhow!("line {number}: {err}"))?;
+    if cards.len() != HAND_SIZE {
+        return Err(anyhow!(
+            "line {number}: a hand has {HAND_SIZE} cards, found {}",
+            cards.len()
+        ));
     }
+    let bid = bid
+        .parse::<u64>()
+        .map_err(|err| anyhow!("line {number}: bid {bid:?}: {err}"))?;
+
+    Ok(Play::new(&cards, bid, number, rules))
+}
+
+fn to_cards(cards: &str) -> Result<Vec<Card>> {
+    cards.chars().map(to_card).collect()
+}
 
-    Play { card_count, bid, cards }
+fn to_card(card: char) -> Result<Card> {
+    match card {
+        'A' => Ok(Card::Ace),
+        'K' => Ok(Card::King),
+        'Q' => Ok(Card::Queen),
+        'J' => Ok(Card::Jack),
+        'T' => Ok(Card::Ten),
+        '9' => Ok(Card::Nine),
+        '8' => Ok(Card::Eight),
+        '7' => Ok(Card::Seven),
+        '6' => Ok(Card::Six),
+        '5' => Ok(Card::Five),
+        '4' => Ok(Card::Four),
+        '3' => Ok(Card::Three),
+        '2' => Ok(Card::Two),
+        _ => Err(anyhow!("{card} is not a valid card symbol")),
+    }
 }
 
-fn to_card(card: char, jack_as_joker: bool) -> Card {
+fn to_symbol(card: Card) -> char {
     match card {
-        'A' => Card::Ace,
-        'K' => Card::King,
-        'Q' => Card::Queen,
-        'J' => if jack_as_joker { Card::Joker } else { Card::Jack },
-        'T' => Card::Ten,
-        '9' => Card::Nine,
-        '8' => Card::Eight,
-        '7' => Card::Seven,
-        '6' => Card::Six,
-        '5' => Card::Five,
-        '4' => Card::Four,
-        '3' => Card::Three,
-        '2' => Card::Two,
-        _ => panic!("not a valid card symbol"),
+        Card::Ace => 'A',
+        Card::King => 'K',
+        Card::Queen => 'Q',
+        Card::Jack => 'J',
+        Card::Ten => 'T',
+        Card::Nine => '9',
+        Card::Eight => '8',
+        Card::Seven => '7',
+        Card::Six => '6',
+        Card::Five => '5',
+        Card::Four => '4',
+        Card::Three => '3',
+        Card::Two => '2',
     }
 }
 
@@ -237,7 +576,63 @@ fn to_card(card: char, jack_as_joker: bool) -> Card {
 // ====================================================
 #[cfg(test)]
 mod test {
-    use crate::parse;
+    use crate::{
+        determine_hand, explain, explain_csv, find_duplicates, parse, rank, to_cards, Card, Hand,
+        Rules, ALL_CARDS, CAMEL_HANDS, HAND_SIZE,
+    };
+
+    // slow but obviously right: try every card in place of each wild card
+    // and keep whichever hand the rules score highest. The hand type doesn't
+    // depend on card order, so wild cards are filled in non-decreasing order
+    // to skip substitutions that are just reorderings of one already tried
+    fn brute_force_hand(rules: &Rules, cards: &[Card]) -> Hand {
+        let mut substituted = cards.to_vec();
+        let mut best = Hand::Empty;
+        substitute(rules, cards, &mut substituted, 0, 0, &mut best);
+        best
+    }
+
+    fn substitute(
+        rules: &Rules,
+        cards: &[Card],
+        substituted: &mut Vec<Card>,
+        position: usize,
+        lowest: usize,
+        best: &mut Hand,
+    ) {
+        if position == cards.len() {
+            let hand = rules.face_value_hand(substituted);
+            if rules.hand_strength(hand) > rules.hand_strength(*best) {
+                *best = hand;
+            }
+            return;
+        }
+
+        if !rules.is_wild(cards[position]) {
+            substitute(rules, cards, substituted, position + 1, lowest, best);
+            return;
+        }
+        for (index, card) in ALL_CARDS.iter().enumerate().skip(lowest) {
+            substituted[position] = *card;
+            substitute(rules, cards, substituted, position + 1, index, best);
+        }
+    }
+
+    // checks the fast path against the brute force for all 13^5 hands
+    fn assert_matches_brute_force(rules: &Rules) {
+        let mut cards = [Card::Two; HAND_SIZE];
+        for mut index in 0..ALL_CARDS.len().pow(HAND_SIZE as u32) {
+            for card in cards.iter_mut() {
+                *card = ALL_CARDS[index % ALL_CARDS.len()];
+                index /= ALL_CARDS.len();
+            }
+            assert_eq!(
+                rules.hand(&cards),
+                brute_force_hand(rules, &cards),
+                "{cards:?} under {rules:?}"
+            );
+        }
+    }
 
     #[test]
     fn day7_simple_case() {
@@ -247,15 +642,15 @@ KK677 28
 KTJJT 220
 QQQJA 483
 ";
-        let mut hands = parse(&s, false);
-        hands.sort_by(|a, z| {
-            a.compare(z)
-        });
+        let mut hands = parse(s, &Rules::camel()).unwrap();
+        rank(&mut hands);
 
         // weakest hand is the lowest rank
-        let total_winnings: u64 = hands.iter().enumerate().map(|(index, play)| {
-            play.bid * ((index + 1) as u64)
-        }).sum();
+        let total_winnings: u64 = hands
+            .iter()
+            .enumerate()
+            .map(|(index, play)| play.bid * ((index + 1) as u64))
+            .sum();
 
         println!("total winnings is {total_winnings}");
 
@@ -270,18 +665,144 @@ KK677 28
 KTJJT 220
 QQQJA 483
 ";
-        let mut hands = parse(&s, true);
-        hands.sort_by(|a, z| {
-            a.compare(z)
-        });
+        let mut hands = parse(s, &Rules::camel_jokers()).unwrap();
+        rank(&mut hands);
 
         // weakest hand is the lowest rank
-        let total_winnings: u64 = hands.iter().enumerate().map(|(index, play)| {
-            play.bid * ((index + 1) as u64)
-        }).sum();
+        let total_winnings: u64 = hands
+            .iter()
+            .enumerate()
+            .map(|(index, play)| play.bid * ((index + 1) as u64))
+            .sum();
 
         println!("total winnings is {total_winnings}");
 
         assert_eq!(total_winnings, 5905);
     }
+
+    #[test]
+    fn day7_count_signatures() {
+        assert_eq!(determine_hand(&[5]), Hand::FiveKind);
+        assert_eq!(determine_hand(&[4, 1]), Hand::FourKind);
+        assert_eq!(determine_hand(&[3, 2]), Hand::FullHouse);
+        assert_eq!(determine_hand(&[3, 1, 1]), Hand::ThreeKind);
+        assert_eq!(determine_hand(&[2, 2, 1]), Hand::TwoPairs);
+        assert_eq!(determine_hand(&[2, 1, 1, 1]), Hand::OnePairs);
+        assert_eq!(determine_hand(&[1, 1, 1, 1, 1]), Hand::HighCard);
+        assert_eq!(determine_hand(&[]), Hand::Empty);
+    }
+
+    #[test]
+    fn day7_rule_variants() {
+        let jokers = Rules::camel_jokers();
+        let hand = |rules: &Rules, cards: &str| rules.hand(&to_cards(cards).unwrap());
+        assert_eq!(hand(&jokers, "JJJJJ"), Hand::FiveKind);
+        assert_eq!(hand(&jokers, "KTJJT"), Hand::FourKind);
+        assert_eq!(hand(&Rules::camel(), "KTJJT"), Hand::TwoPairs);
+
+        // deuces and jacks both wild
+        let deuces = Rules::camel_jokers().with_wild("J2").unwrap();
+        assert_eq!(hand(&deuces, "K2J2A"), Hand::FourKind);
+
+        let straights = Rules::camel_jokers().with_straights().unwrap();
+        assert_eq!(hand(&straights, "6789T"), Hand::Straight);
+        assert_eq!(hand(&straights, "6J89T"), Hand::Straight);
+        assert_eq!(hand(&straights, "6J89A"), Hand::OnePairs);
+        assert_eq!(hand(&straights, "A2345"), Hand::Straight);
+        assert_eq!(hand(&straights, "A2J45"), Hand::Straight);
+        assert_eq!(hand(&straights, "TJQKA"), Hand::Straight);
+        assert_eq!(hand(&straights, "QKA23"), Hand::HighCard);
+        assert!(straights.hand_strength(Hand::Straight) > straights.hand_strength(Hand::ThreeKind));
+        assert!(straights.hand_strength(Hand::Straight) < straights.hand_strength(Hand::FullHouse));
+
+        // poker-style card order changes how ties break, not the hand
+        let poker = Rules::camel_jokers()
+            .with_card_order("23456789TJQKA")
+            .unwrap();
+        assert!(
+            poker.card_strength(to_cards("J").unwrap()[0])
+                > poker.card_strength(to_cards("T").unwrap()[0])
+        );
+        assert_eq!(hand(&poker, "KTJJT"), Hand::FourKind);
+
+        let missing = Rules::new("23456789TJQK", "", CAMEL_HANDS.to_vec()).unwrap_err();
+        assert_eq!(missing.to_string(), "card A is missing a rank");
+        assert!(Rules::camel()
+            .with_hand_order(vec![Hand::FiveKind])
+            .is_err());
+    }
+
+    #[test]
+    fn day7_joker_upgrades_match_brute_force() {
+        assert_matches_brute_force(&Rules::camel_jokers());
+    }
+
+    #[test]
+    fn day7_straights_match_brute_force() {
+        assert_matches_brute_force(&Rules::camel_jokers().with_straights().unwrap());
+    }
+
+    #[test]
+    fn day7_duplicate_hands() {
+        let s = "32T3K 765
+T55J5 684
+32T3K 28
+KTJJT 220
+T55J5 483
+";
+        let mut hands = parse(s, &Rules::camel()).unwrap();
+        assert_eq!(find_duplicates(&hands), vec![(1, 3), (2, 5)]);
+
+        // identical hands are ranked by input order
+        rank(&mut hands);
+        let lines = hands.iter().map(|play| play.line).collect::<Vec<usize>>();
+        assert_eq!(lines, vec![1, 3, 4, 2, 5]);
+    }
+
+    #[test]
+    fn day7_hand_validation() {
+        let rules = Rules::camel();
+        let err = |s: &str| parse(s, &rules).err().unwrap().to_string();
+        assert_eq!(
+            err("32T3K 765\n32T3K2 684"),
+            "line 2: a hand has 5 cards, found 6"
+        );
+        assert_eq!(err("32T3K2Q 684"), "line 1: a hand has 5 cards, found 7");
+        assert_eq!(err("3 1"), "line 1: a hand has 5 cards, found 1");
+        assert_eq!(err("32X3K 765"), "line 1: X is not a valid card symbol");
+        assert_eq!(
+            err("32T3K 765\n\nKK677 28"),
+            "expected \"cards bid\" on line 2, found \"\""
+        );
+        assert!(err("32T3K lots").starts_with("line 1: bid \"lots\""));
+    }
+
+    #[test]
+    fn day7_explain_report() {
+        let s = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+";
+        let mut hands = parse(s, &Rules::camel_jokers()).unwrap();
+        rank(&mut hands);
+
+        let report = explain(&hands);
+        let lines = report.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[0],
+            "rank  line  cards  hand      face value  bid  winnings"
+        );
+        assert_eq!(
+            lines[5],
+            "5     4     KTJJT  FourKind  TwoPairs    220  1100"
+        );
+
+        let csv = explain_csv(&hands);
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "rank,line,cards,hand,face value,bid,winnings");
+        assert_eq!(lines[1], "1,1,32T3K,OnePairs,OnePairs,765,765");
+        assert_eq!(lines.len(), 6);
+    }
 }
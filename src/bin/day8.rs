@@ -1,58 +1,84 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use aoc::{lcm, Part};
-use std::collections::HashMap;
+use clap::{arg, ArgAction};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(-g --graph <format> "export the network as dot or mermaid instead of solving"),
+        arg!(--highlight "highlight the A and Z nodes in the exported graph")
+            .action(ArgAction::SetTrue),
+        arg!(--components "color the nodes reachable from each ghost").action(ArgAction::SetTrue),
+        arg!(--cycles "annotate each origin with the cycle its walk falls into")
+            .action(ArgAction::SetTrue),
+    ])?;
     let s = fs::read_to_string(&problem.path)?;
     let (move_list, map) = parse(&s);
+
+    if let Some(format) = matches.get_one::<String>("graph") {
+        let options = GraphOptions {
+            highlight_ends: matches.get_flag("highlight"),
+            color_components: matches.get_flag("components"),
+            annotate_cycles: matches.get_flag("cycles"),
+        };
+        let format = GraphFormat::new(format)?;
+        print!("{}", export_graph(&map, &move_list, format, &options));
+        return Ok(());
+    }
+
     let mut steps: usize = 0;
     if problem.part == Part::P1 {
         steps = walk(&map, &move_list, ORIGIN, &|location| {
             location.chars().all(|ch| ch == 'Z')
         });
     } else if problem.part == Part::P2 {
-        steps = all_origins(&map)
-            .iter()
-            .map(|origin| {
-                walk(&map, &move_list, origin, &|location| {
-                    location.chars().last().is_some_and(|ch| ch == 'Z')
-                })
-            })
-            .reduce(|acc, steps| lcm(acc, steps))
-            .expect("failed to get lcm for all origins");
+        steps = ghost_steps(&map, &move_list)?;
     }
     println!("steps: {}", steps);
 
     Ok(())
 }
 
-const ORIGIN: &'static str = "AAA";
+const ORIGIN: &str = "AAA";
 
 fn all_origins(map: &HashMap<String, Location>) -> Vec<String> {
-    map.iter()
-        .map(|(origin, _)| origin.clone())
-        .filter(|origin| {
-            origin
-                .chars()
-                .last()
-                .and_then(|ch| Some(ch == 'A'))
-                .unwrap_or(false)
-        })
+    map.keys()
+        .filter(|origin| origin.ends_with('A'))
+        .cloned()
         .collect()
 }
 
+// every ghost walks at once, so they all stand on a Z node together
+// after the lcm of their own walks
+fn ghost_steps(map: &HashMap<String, Location>, move_list: &[Move]) -> Result<usize> {
+    let origins = all_origins(map);
+    if origins.is_empty() {
+        return Err(anyhow!(
+            "failed to get lcm for all origins, no node ends in A"
+        ));
+    }
+
+    origins
+        .iter()
+        .map(|origin| {
+            walk(map, move_list, origin, &|location| {
+                location.chars().last().is_some_and(|ch| ch == 'Z')
+            })
+        })
+        .try_fold(1, lcm)
+}
+
 fn walk(
     map: &HashMap<String, Location>,
-    move_list: &Vec<Move>,
+    move_list: &[Move],
     location: &str,
     is_destination: &dyn Fn(&str) -> bool,
 ) -> usize {
     let mut steps_taken = 0;
     let mut current_location = location.to_string();
     while !is_destination(&current_location) {
-        current_location = take_step(&map, &move_list, steps_taken, &current_location);
+        current_location = take_step(map, move_list, steps_taken, &current_location);
         steps_taken += 1;
     }
 
@@ -61,25 +87,22 @@ fn walk(
 
 fn take_step(
     map: &HashMap<String, Location>,
-    move_list: &Vec<Move>,
+    move_list: &[Move],
     steps_taken: usize,
     current_location: &String,
 ) -> String {
     move_list
         .get(steps_taken % move_list.len())
         .and_then(|mv| {
-            map.get(current_location).and_then(|options| {
+            map.get(current_location).map(|options| {
                 if *mv == Move::Left {
-                    Some(options.0.clone())
+                    options.0.clone()
                 } else {
-                    Some(options.1.clone())
+                    options.1.clone()
                 }
             })
         })
-        .expect(&format!(
-            "failed to get next location, current: {}",
-            current_location
-        ))
+        .unwrap_or_else(|| panic!("failed to get next location, current: {}", current_location))
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -105,7 +128,7 @@ type Location = (String, String);
 // ====================================================
 fn parse(input: &str) -> (Vec<Move>, HashMap<String, Location>) {
     let lines: Vec<&str> = input.lines().collect();
-    let move_list = lines.get(0).expect("no move list").to_string();
+    let move_list = lines.first().expect("no move list").to_string();
 
     let mut moves = vec![];
     for ch in move_list.chars() {
@@ -116,7 +139,7 @@ fn parse(input: &str) -> (Vec<Move>, HashMap<String, Location>) {
     for line in &lines[2..] {
         let location_key_map: Vec<&str> = line.split("=").collect();
         let origin = location_key_map
-            .get(0)
+            .first()
             .expect("key missing")
             .trim()
             .to_string();
@@ -133,7 +156,7 @@ fn parse(input: &str) -> (Vec<Move>, HashMap<String, Location>) {
             origin,
             (
                 travel_options
-                    .get(0)
+                    .first()
                     .expect("missing first travel option")
                     .trim()
                     .to_string(),
@@ -149,13 +172,242 @@ fn parse(input: &str) -> (Vec<Move>, HashMap<String, Location>) {
     (moves, map)
 }
 
+// ====================================================
+//                    Graph Export
+// ====================================================
+const COMPONENT_COLORS: [&str; 8] = [
+    "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5",
+];
+const SHARED_COLOR: &str = "#d9d9d9";
+const ORIGIN_COLOR: &str = "#1a9850";
+const DESTINATION_COLOR: &str = "#d73027";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    fn new(format: &str) -> Result<Self> {
+        match format {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(anyhow!(
+                "unknown graph format {format}, expected dot or mermaid"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct GraphOptions {
+    highlight_ends: bool,
+    color_components: bool,
+    annotate_cycles: bool,
+}
+
+// a ghost's walk is deterministic on (node, position in move list), so it
+// must eventually repeat a state; everything from `start` on loops forever
+#[derive(Debug, Eq, PartialEq)]
+struct Cycle {
+    start: usize,
+    length: usize,
+    // steps, up to the first repeated state, at which the walk stands on a Z node
+    exits: Vec<usize>,
+}
+
+fn find_cycle(map: &HashMap<String, Location>, move_list: &[Move], origin: &str) -> Cycle {
+    let mut seen: HashMap<(String, usize), usize> = HashMap::new();
+    let mut exits = vec![];
+    let mut steps_taken = 0;
+    let mut current_location = origin.to_string();
+    loop {
+        let state = (current_location.clone(), steps_taken % move_list.len());
+        if let Some(start) = seen.get(&state) {
+            return Cycle {
+                start: *start,
+                length: steps_taken - start,
+                exits,
+            };
+        }
+        seen.insert(state, steps_taken);
+        if current_location.ends_with('Z') {
+            exits.push(steps_taken);
+        }
+        current_location = take_step(map, move_list, steps_taken, &current_location);
+        steps_taken += 1;
+    }
+}
+
+fn reachable(map: &HashMap<String, Location>, origin: &str) -> HashSet<String> {
+    let mut visited = HashSet::from([origin.to_string()]);
+    let mut queue = VecDeque::from([origin.to_string()]);
+    while let Some(location) = queue.pop_front() {
+        if let Some((left, right)) = map.get(&location) {
+            for next in [left, right] {
+                if visited.insert(next.clone()) {
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+    }
+
+    visited
+}
+
+// maps each node to the index of the only ghost that can reach it,
+// nodes reachable by more than one ghost are left out
+fn component_owners(map: &HashMap<String, Location>, origins: &[String]) -> HashMap<String, usize> {
+    let mut owners: HashMap<String, Option<usize>> = HashMap::new();
+    for (index, origin) in origins.iter().enumerate() {
+        for location in reachable(map, origin) {
+            owners
+                .entry(location)
+                .and_modify(|owner| *owner = None)
+                .or_insert(Some(index));
+        }
+    }
+
+    owners
+        .into_iter()
+        .filter_map(|(location, owner)| owner.map(|owner| (location, owner)))
+        .collect()
+}
+
+fn export_graph(
+    map: &HashMap<String, Location>,
+    move_list: &[Move],
+    format: GraphFormat,
+    options: &GraphOptions,
+) -> String {
+    let mut locations = map.keys().cloned().collect::<Vec<String>>();
+    locations.sort();
+    let mut origins = all_origins(map);
+    origins.sort();
+
+    let owners = if options.color_components {
+        component_owners(map, &origins)
+    } else {
+        HashMap::new()
+    };
+    let reached_by_any = origins
+        .iter()
+        .flat_map(|origin| reachable(map, origin))
+        .collect::<HashSet<String>>();
+    let cycles = if options.annotate_cycles {
+        origins
+            .iter()
+            .map(|origin| (origin.clone(), find_cycle(map, move_list, origin)))
+            .collect::<HashMap<String, Cycle>>()
+    } else {
+        HashMap::new()
+    };
+
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => out.push_str("digraph network {\n"),
+        GraphFormat::Mermaid => out.push_str("flowchart LR\n"),
+    }
+
+    for location in &locations {
+        let mut label = location.clone();
+        if let Some(cycle) = cycles.get(location) {
+            label.push_str(&format!(
+                "{}cycle at step {} every {} steps, Z at {:?}",
+                match format {
+                    GraphFormat::Dot => "\\n",
+                    GraphFormat::Mermaid => "<br/>",
+                },
+                cycle.start,
+                cycle.length,
+                cycle.exits
+            ));
+        }
+
+        let fill = if !options.color_components {
+            None
+        } else if let Some(owner) = owners.get(location) {
+            Some(COMPONENT_COLORS[owner % COMPONENT_COLORS.len()])
+        } else if reached_by_any.contains(location) {
+            Some(SHARED_COLOR)
+        } else {
+            None
+        };
+        let stroke = if !options.highlight_ends {
+            None
+        } else if location.ends_with('A') {
+            Some(ORIGIN_COLOR)
+        } else if location.ends_with('Z') {
+            Some(DESTINATION_COLOR)
+        } else {
+            None
+        };
+
+        match format {
+            GraphFormat::Dot => {
+                let mut attrs = vec![format!("label=\"{label}\"")];
+                if let Some(fill) = fill {
+                    attrs.push(format!("style=filled, fillcolor=\"{fill}\""));
+                }
+                if let Some(stroke) = stroke {
+                    attrs.push(format!("color=\"{stroke}\", penwidth=3"));
+                }
+                // ids like 11A are not valid bare graphviz ids, so quote them all
+                out.push_str(&format!("    \"{location}\" [{}];\n", attrs.join(", ")));
+            }
+            GraphFormat::Mermaid => {
+                out.push_str(&format!("    {location}[\"{label}\"]\n"));
+                let mut styles = vec![];
+                if let Some(fill) = fill {
+                    styles.push(format!("fill:{fill}"));
+                }
+                if let Some(stroke) = stroke {
+                    styles.push(format!("stroke:{stroke},stroke-width:3px"));
+                }
+                if !styles.is_empty() {
+                    out.push_str(&format!("    style {location} {}\n", styles.join(",")));
+                }
+            }
+        }
+    }
+
+    for location in &locations {
+        let (left, right) = &map[location];
+        // a node whose options are the same only gets one edge
+        let edges = if left == right {
+            vec![("LR", left)]
+        } else {
+            vec![("L", left), ("R", right)]
+        };
+        for (label, next) in edges {
+            match format {
+                GraphFormat::Dot => out.push_str(&format!(
+                    "    \"{location}\" -> \"{next}\" [label=\"{label}\"];\n"
+                )),
+                GraphFormat::Mermaid => {
+                    out.push_str(&format!("    {location} -->|{label}| {next}\n"))
+                }
+            }
+        }
+    }
+
+    if format == GraphFormat::Dot {
+        out.push_str("}\n");
+    }
+
+    out
+}
+
 // ====================================================
 //                      Unit Tests
 // ====================================================
 #[cfg(test)]
 mod test {
-    use aoc::lcm;
-    use crate::{parse, walk, ORIGIN, all_origins};
+    use crate::{
+        export_graph, find_cycle, ghost_steps, parse, walk, Cycle, GraphFormat, GraphOptions,
+        ORIGIN,
+    };
 
     #[test]
     fn day8_two_move() {
@@ -168,7 +420,7 @@ DDD = (DDD, DDD)
 EEE = (EEE, EEE)
 GGG = (GGG, GGG)
 ZZZ = (ZZZ, ZZZ)";
-        let (move_list, map) = parse(&s);
+        let (move_list, map) = parse(s);
         let steps = walk(&map, &move_list, ORIGIN, &|location| {
             location.chars().all(|ch| ch == 'Z')
         });
@@ -182,7 +434,7 @@ ZZZ = (ZZZ, ZZZ)";
 AAA = (BBB, BBB)
 BBB = (AAA, ZZZ)
 ZZZ = (ZZZ, ZZZ)";
-        let (move_list, map) = parse(&s);
+        let (move_list, map) = parse(s);
         let steps = walk(&map, &move_list, ORIGIN, &|location| {
             location.chars().all(|ch| ch == 'Z')
         });
@@ -201,17 +453,90 @@ ZZZ = (ZZZ, ZZZ)";
 22C = (22Z, 22Z)
 22Z = (22B, 22B)
 XXX = (XXX, XXX)";
-        let (move_list, map) = parse(&s);
-        let steps = all_origins(&map)
-            .iter()
-            .map(|origin| {
-                walk(&map, &move_list, origin, &|location| {
-                    location.chars().last().is_some_and(|ch| ch == 'Z')
-                })
-            })
-            .reduce(|acc, steps| lcm(acc, steps))
-            .expect("failed to get lcm for all origins");
+        let (move_list, map) = parse(s);
+        let steps = ghost_steps(&map, &move_list).expect("failed to get lcm for all origins");
+        assert_eq!(steps, 6);
 
-        println!("steps: {}", steps);
+        let s = "L
+
+BBB = (CCC, CCC)
+CCC = (BBB, BBB)";
+        let (move_list, map) = parse(s);
+        let err = ghost_steps(&map, &move_list).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to get lcm for all origins, no node ends in A"
+        );
+    }
+
+    #[test]
+    fn day8_cycle_per_origin() {
+        let s = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+        let (move_list, map) = parse(s);
+        assert_eq!(
+            find_cycle(&map, &move_list, "11A"),
+            Cycle {
+                start: 1,
+                length: 2,
+                exits: vec![2]
+            }
+        );
+        assert_eq!(
+            find_cycle(&map, &move_list, "22A"),
+            Cycle {
+                start: 1,
+                length: 6,
+                exits: vec![3, 6]
+            }
+        );
+    }
+
+    #[test]
+    fn day8_graph_export() {
+        let s = "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+        let (move_list, map) = parse(s);
+        let options = GraphOptions {
+            highlight_ends: true,
+            color_components: true,
+            annotate_cycles: true,
+        };
+
+        let dot = export_graph(&map, &move_list, GraphFormat::Dot, &options);
+        assert!(dot.starts_with("digraph network {\n"), "{dot}");
+        assert!(dot.contains("    \"AAA\" [label="), "{dot}");
+        assert!(
+            dot.contains("    \"AAA\" -> \"BBB\" [label=\"LR\"];\n"),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("    \"BBB\" -> \"ZZZ\" [label=\"R\"];\n"),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("AAA\\ncycle at step 6 every 3 steps, Z at [6, 7, 8]"),
+            "{dot}"
+        );
+        assert!(dot.ends_with("}\n"), "{dot}");
+
+        let mermaid = export_graph(&map, &move_list, GraphFormat::Mermaid, &options);
+        assert!(mermaid.starts_with("flowchart LR\n"), "{mermaid}");
+        assert!(mermaid.contains("    BBB -->|L| AAA\n"), "{mermaid}");
+        assert!(
+            mermaid.contains("    style ZZZ fill:#8dd3c7,stroke:#d73027,stroke-width:3px\n"),
+            "{mermaid}"
+        );
     }
 }
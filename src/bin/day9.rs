@@ -1,67 +1,708 @@
-use anyhow::Result;
-use aoc::Part;
-use std::fs;
+use anyhow::{anyhow, Context, Result};
+use aoc::{gcd, overflow, BigInt, Part, Rational, Signed};
+use clap::{arg, ArgAction};
+use std::{fmt::Display, fs};
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone, Copy)]
 enum Direction {
     Forward,
     Backward,
 }
 
+// what to try when a history's differences never vanish
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum Fallback {
+    // each value a fixed multiple of the one before
+    Geometric,
+    // each value a fixed combination of the ones before
+    Recurrence,
+}
+
+impl Fallback {
+    fn new(name: &str) -> Result<Self> {
+        match name {
+            "geometric" => Ok(Fallback::Geometric),
+            "recurrence" => Ok(Fallback::Recurrence),
+            _ => Err(anyhow!(
+                "unknown fallback {name}, expected geometric or recurrence"
+            )),
+        }
+    }
+
+    // a geometric sequence is just a recurrence of order 1
+    fn max_order(&self, len: usize) -> usize {
+        match self {
+            Fallback::Geometric => 1,
+            Fallback::Recurrence => len,
+        }
+    }
+}
+
+// a history the difference pyramid runs out on before reaching a row of
+// zeros, so no polynomial of a degree it can confirm goes through it
+#[derive(Debug)]
+struct NotPolynomial {
+    degree: usize,
+}
+
+impl Display for NotPolynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "differences never vanish, still nonzero at degree {}",
+            self.degree
+        )
+    }
+}
+
+impl std::error::Error for NotPolynomial {}
+
+// a blank line, which has no values to fit anything through
+#[derive(Debug)]
+struct EmptyHistory;
+
+impl Display for EmptyHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "empty history, there's nothing to extrapolate from")
+    }
+}
+
+impl std::error::Error for EmptyHistory {}
+
 fn main() -> Result<()> {
-    let problem = aoc::fetch_problem()?;
+    let (problem, matches) = aoc::fetch_problem_with_args(vec![
+        arg!(--steps <steps> "how many steps past the end of each history to predict (default 1)"),
+        arg!(--fit "print the polynomial fitted to each history").action(ArgAction::SetTrue),
+        arg!(--show "print each history's pyramid of differences").action(ArgAction::SetTrue),
+        arg!(--width <width> "solve with 64 or 128 bit integers, or big for no limit (default 64)"),
+        arg!(--fallback <model> "geometric or recurrence, for histories that aren't polynomials"),
+    ])?;
+    let steps = matches
+        .get_one::<String>("steps")
+        .map(|steps| steps.parse::<i64>())
+        .transpose()?
+        .unwrap_or(1);
+    let fit = matches.get_flag("fit");
+    let show = matches.get_flag("show");
+    let fallback = matches
+        .get_one::<String>("fallback")
+        .map(|name| Fallback::new(name))
+        .transpose()?;
     let input = fs::read_to_string(problem.path)?;
-    let predictive_sum: i32 = parse(&input)
-        .iter()
-        .map(|history| {
-            extrapolate(
-                history,
-                if problem.part == Part::P2 {
-                    Direction::Backward
-                } else {
-                    Direction::Forward
-                },
-            )
-        })
-        .sum();
+    let direction = if problem.part == Part::P2 {
+        Direction::Backward
+    } else {
+        Direction::Forward
+    };
+
+    let predictive_sum = match matches.get_one::<String>("width").map(|s| &s[..]) {
+        None | Some("64") => {
+            solve::<i64>(&input, steps, direction, fallback, fit, show)?.to_string()
+        }
+        Some("128") => solve::<i128>(&input, steps, direction, fallback, fit, show)?.to_string(),
+        Some("big") => solve::<BigInt>(&input, steps, direction, fallback, fit, show)?.to_string(),
+        Some(width) => {
+            return Err(anyhow!(
+                "unsupported width {width}, expected 64, 128 or big"
+            ))
+        }
+    };
     println!("predictive sum: {predictive_sum}");
     Ok(())
 }
 
-fn extrapolate(history: &Vec<i32>, direction: Direction) -> i32 {
-    // base case
-    if history.iter().all(|x| *x == 0) {
-        return 0;
+fn solve<T: Signed>(
+    input: &str,
+    steps: i64,
+    direction: Direction,
+    fallback: Option<Fallback>,
+    fit: bool,
+    show: bool,
+) -> Result<T> {
+    let histories = parse::<T>(input)?;
+    if show {
+        for (index, history) in histories.iter().enumerate() {
+            // a history that can't be extended still gets its pyramid shown,
+            // the error comes out when it's summed below
+            let predictions = (1..=steps)
+                .map(|steps| extrapolate(history, steps, direction, fallback))
+                .collect::<Result<Vec<T>>>()
+                .unwrap_or_default();
+            let pyramid = pyramid(history, &predictions, direction)
+                .with_context(|| format!("history on line {}", index + 1))?;
+            println!("line {}:\n{pyramid}", index + 1);
+        }
+    }
+    if fit {
+        for (index, history) in histories.iter().enumerate() {
+            let newton =
+                Newton::new(history).with_context(|| format!("history on line {}", index + 1))?;
+            if let Some(degree) = newton.unconfirmed_degree() {
+                let recurrence = match fallback {
+                    Some(fallback) => Recurrence::fit(history, fallback.max_order(history.len()))
+                        .map_or_else(
+                            |err| format!(", {err}"),
+                            |recurrence| match recurrence {
+                                Some(recurrence) => format!(", {}", recurrence.formula()),
+                                None => format!(", no {fallback:?} fit").to_lowercase(),
+                            },
+                        ),
+                    None => String::new(),
+                };
+                println!(
+                    "line {}: {}{recurrence}",
+                    index + 1,
+                    NotPolynomial { degree }
+                );
+                continue;
+            }
+            let degree = newton.degree().map_or("-".to_string(), |d| d.to_string());
+            let polynomial = newton
+                .coefficients()
+                .map_or_else(|err| err.to_string(), |fitted| format_polynomial(&fitted));
+            println!("line {}: degree {degree}, {polynomial}", index + 1);
+        }
     }
 
-    let diff = history
-        .iter()
-        .zip(history.iter().skip(1))
-        .map(|(prev, next)| next - prev)
-        .collect::<Vec<i32>>();
+    let mut predictive_sum = T::zero();
+    for (index, history) in histories.iter().enumerate() {
+        let line = index + 1;
+        let prediction = extrapolate(history, steps, direction, fallback)
+            .with_context(|| format!("history on line {line}"))?;
+        predictive_sum = predictive_sum
+            .checked_add(&prediction)
+            .ok_or_else(|| overflow::<T>(format!("predictive sum up to line {line}")))?;
+    }
+
+    Ok(predictive_sum)
+}
+
+fn extrapolate<T: Signed>(
+    history: &[T],
+    steps: i64,
+    direction: Direction,
+    fallback: Option<Fallback>,
+) -> Result<T> {
+    let newton = Newton::new(history)?;
+    let Some(degree) = newton.unconfirmed_degree() else {
+        return newton.extrapolate(steps, direction);
+    };
+
+    let Some(fallback) = fallback else {
+        return Err(NotPolynomial { degree }.into());
+    };
+    match Recurrence::fit(history, fallback.max_order(history.len()))? {
+        Some(recurrence) => recurrence.extrapolate(steps, direction),
+        None => Err(anyhow::Error::new(NotPolynomial { degree })
+            .context(format!("no {fallback:?} model fits either").to_lowercase())),
+    }
+}
+
+// ====================================================
+//                    Newton Form
+// ====================================================
+
+// the first value of every row of the difference pyramid. Any history
+// is history[x] = sum over k of leading[k] * C(x, k), which extends to
+// every integer x, so predicting in either direction is one sum
+struct Newton<T> {
+    leading: Vec<T>,
+    len: usize,
+    // whether the pyramid reached a row of zeros before running out
+    vanished: bool,
+}
+
+impl<T: Signed> Newton<T> {
+    // turns the history into its differences in place, row by row,
+    // keeping the first entry of each row, O(n^2) and no extra rows
+    fn new(history: &[T]) -> Result<Self> {
+        if history.is_empty() {
+            return Err(EmptyHistory.into());
+        }
+        let mut row = history.to_vec();
+        let mut leading = Vec::with_capacity(row.len());
+        let mut vanished = false;
+        for width in (1..=row.len()).rev() {
+            leading.push(row[0].clone());
+            if row[..width].iter().all(|x| x.is_zero()) {
+                vanished = true;
+                break;
+            }
+            for i in 0..width - 1 {
+                row[i] = row[i + 1].checked_sub(&row[i]).ok_or_else(|| {
+                    overflow::<T>(format!("difference {} - {}", row[i + 1], row[i]))
+                })?;
+            }
+        }
+
+        // trailing zero rows say nothing about the polynomial
+        while leading.last().is_some_and(|x| x.is_zero()) {
+            leading.pop();
+        }
+
+        Ok(Self {
+            leading,
+            len: history.len(),
+            vanished,
+        })
+    }
+
+    // None for a history of all zeros
+    fn degree(&self) -> Option<usize> {
+        self.leading.len().checked_sub(1)
+    }
+
+    // the degree the pyramid got to without finding a row of zeros,
+    // None when it did find one and the polynomial is confirmed
+    fn unconfirmed_degree(&self) -> Option<usize> {
+        (!self.vanished).then(|| self.len - 1)
+    }
+
+    // the value at any index, negative ones are before the history starts
+    fn at(&self, x: i64) -> Result<T> {
+        let overflowed = || overflow::<T>(format!("value at index {x}"));
+        let mut value = T::zero();
+        let mut binomial = T::from_i64(1); // C(x, k)
+        for (k, leading) in self.leading.iter().enumerate() {
+            if k > 0 {
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, always divides evenly
+                binomial = T::from_i64(x)
+                    .checked_sub(&T::from_i64(k as i64 - 1))
+                    .and_then(|factor| binomial.checked_mul(&factor))
+                    .and_then(|binomial| binomial.checked_div_exact(k as i64))
+                    .ok_or_else(overflowed)?;
+            }
+            value = leading
+                .checked_mul(&binomial)
+                .and_then(|term| value.checked_add(&term))
+                .ok_or_else(overflowed)?;
+        }
+
+        Ok(value)
+    }
+
+    fn extrapolate(&self, steps: i64, direction: Direction) -> Result<T> {
+        let last = self.len as i64 - 1;
+        let x = match direction {
+            Direction::Forward => last
+                .checked_add(steps)
+                .ok_or_else(|| overflow::<i64>(format!("index {last} + {steps} steps")))?,
+            Direction::Backward => steps
+                .checked_neg()
+                .ok_or_else(|| overflow::<i64>(format!("index -{steps} steps")))?,
+        };
+        self.at(x)
+    }
 
-    // recursive case
-    return if direction == Direction::Forward {
-        history
+    // the polynomial through the history, lowest power first, as integer
+    // numerators over one shared denominator in lowest terms
+    fn coefficients(&self) -> Result<(Vec<i128>, i128)> {
+        let overflowed = || overflow::<i128>("fitted polynomial".to_string());
+        // leading[k] * C(x, k) = leading[k] * (x)(x - 1)...(x - k + 1) / k!,
+        // so scale every term up to the largest factorial
+        let degree = self.leading.len().saturating_sub(1);
+        let denominator = (1..=degree as i128)
+            .try_fold(1i128, |product, k| product.checked_mul(k))
+            .ok_or_else(overflowed)?;
+        let mut numerators = vec![0i128; degree + 1];
+        let mut falling = vec![1i128]; // (x)(x - 1)...(x - k + 1)
+        let mut factorial = 1i128;
+        for (k, leading) in self.leading.iter().enumerate() {
+            if k > 0 {
+                factorial *= k as i128;
+                let mut next = vec![0i128; k + 1];
+                for (power, coefficient) in falling.iter().copied().enumerate() {
+                    next[power + 1] = next[power + 1]
+                        .checked_add(coefficient)
+                        .ok_or_else(overflowed)?;
+                    next[power] = coefficient
+                        .checked_mul(k as i128 - 1)
+                        .and_then(|product| next[power].checked_sub(product))
+                        .ok_or_else(overflowed)?;
+                }
+                falling = next;
+            }
+            let scale = leading
+                .to_i128()
+                .and_then(|leading| leading.checked_mul(denominator / factorial))
+                .ok_or_else(overflowed)?;
+            for (power, coefficient) in falling.iter().enumerate() {
+                numerators[power] = scale
+                    .checked_mul(*coefficient)
+                    .and_then(|term| numerators[power].checked_add(term))
+                    .ok_or_else(overflowed)?;
+            }
+        }
+
+        let divisor = numerators
             .iter()
-            .last()
-            .expect("missing last point in history!")
-            + extrapolate(&diff, direction)
+            .fold(denominator.unsigned_abs(), |divisor, numerator| {
+                gcd(divisor, numerator.unsigned_abs())
+            }) as i128;
+        Ok((
+            numerators
+                .iter()
+                .map(|numerator| numerator / divisor)
+                .collect(),
+            denominator / divisor,
+        ))
+    }
+}
+
+// e.g. (30 + 11x - 3x^2 + x^3) / 3
+fn format_polynomial((numerators, denominator): &(Vec<i128>, i128)) -> String {
+    let mut terms = String::new();
+    for (power, coefficient) in numerators.iter().enumerate() {
+        if *coefficient == 0 && numerators.len() > 1 {
+            continue;
+        }
+        let magnitude = coefficient.unsigned_abs();
+        let variable = match power {
+            0 => String::new(),
+            1 => "x".to_string(),
+            _ => format!("x^{power}"),
+        };
+        let number = if magnitude == 1 && power > 0 {
+            String::new()
+        } else {
+            magnitude.to_string()
+        };
+        if terms.is_empty() {
+            let sign = if *coefficient < 0 { "-" } else { "" };
+            terms.push_str(&format!("{sign}{number}{variable}"));
+        } else {
+            let sign = if *coefficient < 0 { '-' } else { '+' };
+            terms.push_str(&format!(" {sign} {number}{variable}"));
+        }
+    }
+
+    if *denominator == 1 {
+        terms
     } else {
-        history.get(0).expect("missing first point in history!") - extrapolate(&diff, direction)
+        format!("({terms}) / {denominator}")
+    }
+}
+
+// ====================================================
+//                 Difference Pyramid
+// ====================================================
+
+// the history and its rows of differences down to the first row of zeros,
+// staggered so each difference sits between the two values it came from.
+// Predictions extend every row on the side they were made, in brackets
+fn pyramid<T: Signed>(history: &[T], predictions: &[T], direction: Direction) -> Result<String> {
+    let mut row = match direction {
+        Direction::Forward => [history, predictions].concat(),
+        Direction::Backward => predictions.iter().rev().chain(history).cloned().collect(),
     };
+    let mut rows = vec![];
+    while !row.is_empty() {
+        let extra = predictions.len().min(row.len());
+        let cells = row.iter().enumerate().map(|(i, value)| {
+            let predicted = match direction {
+                Direction::Forward => i >= row.len() - extra,
+                Direction::Backward => i < extra,
+            };
+            if predicted {
+                format!("[{value}]")
+            } else {
+                value.to_string()
+            }
+        });
+        rows.push(cells.collect::<Vec<String>>());
+        if row.len() == 1 || row.iter().all(|x| x.is_zero()) {
+            break;
+        }
+        row = row
+            .windows(2)
+            .map(|pair| {
+                pair[1]
+                    .checked_sub(&pair[0])
+                    .ok_or_else(|| overflow::<T>(format!("difference {} - {}", pair[1], pair[0])))
+            })
+            .collect::<Result<Vec<T>>>()?;
+    }
+
+    // an even width so rows can be shifted by half a cell
+    let widest = rows
+        .iter()
+        .flatten()
+        .map(|cell| cell.len())
+        .max()
+        .unwrap_or(0);
+    let width = (widest + 2) / 2 * 2;
+    Ok(rows
+        .iter()
+        .enumerate()
+        .map(|(depth, cells)| {
+            let mut line = " ".repeat(depth * width / 2);
+            for cell in cells {
+                line.push_str(&format!("{cell:>width$}"));
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+// ====================================================
+//                 Linear Recurrences
+// ====================================================
+
+// history[n] = coefficients[0] * history[n - 1] + ...
+//            + coefficients[k - 1] * history[n - k]
+// worked out in i128 fractions, whatever width the history came in
+struct Recurrence {
+    coefficients: Vec<Rational>,
+    history: Vec<i128>,
+}
+
+impl Recurrence {
+    // the shortest recurrence that reproduces the whole history, if its
+    // order is at most max_order. An order k recurrence is only pinned
+    // down by 2k values, so it needs at least one more to be confirmed by
+    fn fit<T: Signed>(history: &[T], max_order: usize) -> Result<Option<Self>> {
+        let history = history
+            .iter()
+            .map(|x| {
+                x.to_i128()
+                    .ok_or_else(|| overflow::<i128>(format!("number {x}")))
+            })
+            .collect::<Result<Vec<i128>>>()?;
+        let coefficients = Self::shortest(&history)?;
+        let order = coefficients.len();
+        if order == 0 || order > max_order || 2 * order >= history.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            coefficients,
+            history,
+        }))
+    }
+
+    // Berlekamp-Massey: walks the history once, and whenever the current
+    // recurrence mispredicts a value, corrects it with a multiple of the
+    // last one that failed, growing it only as much as it has to. The
+    // connection polynomial is 1 - coefficients[0] x - coefficients[1] x^2 ...
+    fn shortest(history: &[i128]) -> Result<Vec<Rational>> {
+        let overflowed = || overflow::<i128>("shortest linear recurrence".to_string());
+        let mut connection = vec![Rational::ONE];
+        let mut previous = vec![Rational::ONE];
+        let mut previous_discrepancy = Rational::ONE;
+        let mut order = 0;
+        let mut shift = 1;
+        for n in 0..history.len() {
+            // how far off the current recurrence is at n
+            let discrepancy = connection
+                .iter()
+                .enumerate()
+                .take(order + 1)
+                .try_fold(Rational::ZERO, |sum, (back, coefficient)| {
+                    coefficient
+                        .checked_mul(Rational::integer(history[n - back]))
+                        .and_then(|term| sum.checked_add(term))
+                })
+                .ok_or_else(overflowed)?;
+            if discrepancy.is_zero() {
+                shift += 1;
+                continue;
+            }
+
+            let scale = discrepancy
+                .checked_div(previous_discrepancy)
+                .ok_or_else(overflowed)?;
+            let mut corrected = connection.clone();
+            corrected.resize(corrected.len().max(previous.len() + shift), Rational::ZERO);
+            for (power, coefficient) in previous.iter().enumerate() {
+                let entry = &mut corrected[power + shift];
+                *entry = scale
+                    .checked_mul(*coefficient)
+                    .and_then(|term| entry.checked_sub(term))
+                    .ok_or_else(overflowed)?;
+            }
+
+            if 2 * order <= n {
+                order = n + 1 - order;
+                previous = connection;
+                previous_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+            connection = corrected;
+        }
+
+        connection.resize(order + 1, Rational::ZERO);
+        connection[1..]
+            .iter()
+            .map(|coefficient| {
+                Rational::ZERO
+                    .checked_sub(*coefficient)
+                    .ok_or_else(overflowed)
+            })
+            .collect()
+    }
+
+    // the matrix that moves a window of k consecutive values, oldest first,
+    // one step in the direction. Forward shifts the window along and appends
+    // the recurrence, backward solves the recurrence for the oldest term
+    fn step_matrix(&self, direction: Direction) -> Result<Matrix> {
+        let overflowed = || overflow::<i128>("recurrence step".to_string());
+        let order = self.coefficients.len();
+        let mut matrix = vec![vec![Rational::ZERO; order]; order];
+        match direction {
+            Direction::Forward => {
+                for row in 0..order - 1 {
+                    matrix[row][row + 1] = Rational::ONE;
+                }
+                for (back, coefficient) in self.coefficients.iter().enumerate() {
+                    matrix[order - 1][order - 1 - back] = *coefficient;
+                }
+            }
+            Direction::Backward => {
+                let (oldest, rest) = self.coefficients.split_last().unwrap();
+                if oldest.is_zero() {
+                    return Err(anyhow!("the recurrence can't be run backwards"));
+                }
+                matrix[0][order - 1] = Rational::ONE.checked_div(*oldest).ok_or_else(overflowed)?;
+                for (back, coefficient) in rest.iter().enumerate() {
+                    matrix[0][order - 2 - back] = Rational::ZERO
+                        .checked_sub(*coefficient)
+                        .and_then(|negated| negated.checked_div(*oldest))
+                        .ok_or_else(overflowed)?;
+                }
+                for row in 1..order {
+                    matrix[row][row - 1] = Rational::ONE;
+                }
+            }
+        }
+
+        Ok(matrix)
+    }
+
+    // the window `steps` away is the step matrix to that power times the
+    // window at the edge of the history, and squaring gets the power in
+    // O(k^3 log steps). Only the value asked for has to be a whole number
+    fn extrapolate<T: Signed>(&self, steps: i64, direction: Direction) -> Result<T> {
+        let overflowed = || overflow::<i128>(format!("recurrence {steps} steps out"));
+        let order = self.coefficients.len();
+        if steps <= 0 {
+            return Err(anyhow!("a recurrence only predicts past the history"));
+        }
+        let window = match direction {
+            Direction::Forward => &self.history[self.history.len() - order..],
+            Direction::Backward => &self.history[..order],
+        };
+
+        let mut power = self.step_matrix(direction)?;
+        let mut moved = identity(order);
+        let mut remaining = steps;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                moved = multiply(&moved, &power).ok_or_else(overflowed)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                power = multiply(&power, &power).ok_or_else(overflowed)?;
+            }
+        }
+
+        let row = match direction {
+            Direction::Forward => &moved[order - 1],
+            Direction::Backward => &moved[0],
+        };
+        let value = row
+            .iter()
+            .zip(window)
+            .try_fold(Rational::ZERO, |sum, (entry, value)| {
+                entry
+                    .checked_mul(Rational::integer(*value))
+                    .and_then(|term| sum.checked_add(term))
+            })
+            .ok_or_else(overflowed)?;
+        let value = value
+            .to_integer()
+            .ok_or_else(|| anyhow!("the recurrence predicts the fraction {value}"))?;
+        T::from_i128(value).ok_or_else(|| overflow::<T>(format!("prediction {value}")))
+    }
+
+    // e.g. x[n] = x[n-1] + x[n-2]
+    fn formula(&self) -> String {
+        let mut terms = String::new();
+        for (back, coefficient) in self.coefficients.iter().enumerate() {
+            if coefficient.is_zero() && self.coefficients.len() > 1 {
+                continue;
+            }
+            let negative = coefficient.numer() < 0;
+            let magnitude = coefficient.numer().unsigned_abs();
+            let number = match coefficient.denom() {
+                1 if magnitude == 1 => String::new(),
+                1 => magnitude.to_string(),
+                denom => format!("({magnitude}/{denom})"),
+            };
+            let term = format!("{number}x[n-{}]", back + 1);
+            if terms.is_empty() {
+                terms.push_str(if negative { "-" } else { "" });
+                terms.push_str(&term);
+            } else {
+                terms.push_str(if negative { " - " } else { " + " });
+                terms.push_str(&term);
+            }
+        }
+
+        format!("x[n] = {terms}")
+    }
+}
+
+type Matrix = Vec<Vec<Rational>>;
+
+fn identity(size: usize) -> Matrix {
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|column| {
+                    if row == column {
+                        Rational::ONE
+                    } else {
+                        Rational::ZERO
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+    a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|column| {
+                    row.iter()
+                        .zip(b)
+                        .try_fold(Rational::ZERO, |sum, (entry, b_row)| {
+                            entry
+                                .checked_mul(b_row[column])
+                                .and_then(|term| sum.checked_add(term))
+                        })
+                })
+                .collect()
+        })
+        .collect()
 }
 
 // ====================================================
 //                      Parsing
 // ====================================================
-fn parse(input: &str) -> Vec<Vec<i32>> {
+fn parse<T: Signed>(input: &str) -> Result<Vec<Vec<T>>> {
     input
         .lines()
-        .map(|line| {
+        .enumerate()
+        .map(|(index, line)| {
             line.split_whitespace()
-                .map(|num| num.parse::<i32>().expect("failed to parse history point"))
-                .collect()
+                .map(T::parse)
+                .collect::<Result<Vec<T>>>()
+                .with_context(|| format!("history on line {}", index + 1))
         })
         .collect()
 }
@@ -71,18 +712,24 @@ fn parse(input: &str) -> Vec<Vec<i32>> {
 // ====================================================
 #[cfg(test)]
 mod test {
-    use crate::{extrapolate, parse, Direction};
+    use crate::{
+        extrapolate, format_polynomial, parse, pyramid, solve, Direction, EmptyHistory, Fallback,
+        Newton, NotPolynomial, Recurrence,
+    };
+    use aoc::{BigInt, Overflow, Signed};
 
     #[test]
     fn day9_forward_case() {
         let s = "0 3 6 9 12 15
 1 3 6 10 15 21
 10 13 16 21 30 45";
-        let predictive_sum: i32 = parse(&s)
+        let predictive_sum: i64 = parse::<i64>(s)
+            .unwrap()
             .iter()
-            .map(|history| extrapolate(history, Direction::Forward))
+            .map(|history| extrapolate(history, 1, Direction::Forward, None).unwrap())
             .sum();
         println!("predictive sum: {predictive_sum}");
+        assert_eq!(predictive_sum, 114);
     }
 
     #[test]
@@ -90,10 +737,364 @@ mod test {
         let s = "0 3 6 9 12 15
 1 3 6 10 15 21
 10 13 16 21 30 45";
-        let predictive_sum: i32 = parse(&s)
+        let predictive_sum: i64 = parse::<i64>(s)
+            .unwrap()
             .iter()
-            .map(|history| extrapolate(history, Direction::Backward))
+            .map(|history| extrapolate(history, 1, Direction::Backward, None).unwrap())
             .sum();
         println!("predictive sum: {predictive_sum}");
+        assert_eq!(predictive_sum, 2);
+    }
+
+    #[test]
+    fn day9_newton_form() {
+        // triangular numbers, (x^2 + 3x + 2) / 2
+        let newton = Newton::new(&[1i64, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(newton.degree(), Some(2));
+        assert_eq!(newton.coefficients().unwrap(), (vec![2, 3, 1], 2));
+        assert_eq!(newton.extrapolate(1, Direction::Forward).unwrap(), 28);
+        assert_eq!(newton.extrapolate(3, Direction::Forward).unwrap(), 45);
+        assert_eq!(newton.extrapolate(1, Direction::Backward).unwrap(), 0);
+        assert_eq!(newton.extrapolate(4, Direction::Backward).unwrap(), 3);
+
+        // 10 13 16 21 30 45 is x^3 / 3 - x^2 + 11x / 3 + 10
+        let newton = Newton::new(&[10i64, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(newton.degree(), Some(3));
+        assert_eq!(newton.coefficients().unwrap(), (vec![30, 11, -3, 1], 3));
+        assert_eq!(
+            format_polynomial(&newton.coefficients().unwrap()),
+            "(30 + 11x - 3x^2 + x^3) / 3"
+        );
+        assert_eq!(newton.at(-1).unwrap(), 5);
+
+        let newton = Newton::new(&[7i64, 7, 7]).unwrap();
+        assert_eq!(newton.degree(), Some(0));
+        assert_eq!(newton.coefficients().unwrap(), (vec![7], 1));
+        assert_eq!(format_polynomial(&newton.coefficients().unwrap()), "7");
+        assert_eq!(
+            newton
+                .extrapolate(i64::MAX - 2, Direction::Forward)
+                .unwrap(),
+            7
+        );
+        let err = newton
+            .extrapolate(i64::MAX, Direction::Forward)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "index 2 + 9223372036854775807 steps overflows i64"
+        );
+        let err = newton
+            .extrapolate(i64::MIN, Direction::Backward)
+            .unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+
+        // cubes, (x + 1)^3, as far back as the steps go
+        let cubes = [1, 8, 27, 64, 125, 216];
+        let newton = Newton::new(&cubes.map(i128::from)).unwrap();
+        let err = newton
+            .extrapolate(i64::MAX, Direction::Backward)
+            .unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        let newton = Newton::new(&cubes.map(BigInt::from_i64)).unwrap();
+        let root = BigInt::from_i64(1 - i64::MAX);
+        assert_eq!(
+            newton.extrapolate(i64::MAX, Direction::Backward).unwrap(),
+            root.checked_mul(&root)
+                .and_then(|square| square.checked_mul(&root))
+                .unwrap()
+        );
+        assert_eq!(
+            format_polynomial(&Newton::new(&[0i64, -1]).unwrap().coefficients().unwrap()),
+            "-x"
+        );
+        assert_eq!(Newton::new(&[0i64, 0]).unwrap().degree(), None);
+        let err = Newton::<i64>::new(&[]).err().unwrap();
+        assert!(err.is::<EmptyHistory>(), "{err}");
+
+        // a blank line is an error with its line number, not a silent 0
+        let err = solve::<i64>("\n1 2 3", 1, Direction::Forward, None, false, false).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "history on line 1: empty history, there's nothing to extrapolate from"
+        );
+    }
+
+    #[test]
+    fn day9_widths() {
+        // the differences already step outside i64
+        let s = "1 2 3
+4611686018427387904 9223372036854775807 0";
+        let err = solve::<i64>(s, 1, Direction::Forward, None, false, false).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert!(
+            format!("{err:#}").starts_with("history on line 2: "),
+            "{err:#}"
+        );
+
+        // each prediction fits, their sum of 2^63 doesn't
+        let s = "4611686018427387904 4611686018427387904
+4611686018427387904 4611686018427387904";
+        let err = solve::<i64>(s, 40, Direction::Forward, None, false, false).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "predictive sum up to line 2 overflows i64"
+        );
+        assert_eq!(
+            solve::<i128>(s, 40, Direction::Forward, None, false, false).unwrap(),
+            9223372036854775808
+        );
+
+        // x^30 outgrows i128 well before the pyramid bottoms out
+        let power = |x: i64| {
+            (0..30).fold(BigInt::from_i64(1), |value, _| {
+                value.checked_mul(&BigInt::from_i64(x)).unwrap()
+            })
+        };
+        let powers = (0..35)
+            .map(|x| power(x).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        let err = solve::<i128>(&powers, 1, Direction::Forward, None, false, false).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert_eq!(
+            solve::<BigInt>(&powers, 1, Direction::Forward, None, false, false).unwrap(),
+            power(35)
+        );
+
+        let s = "-1 -4 -9 -16";
+        assert_eq!(
+            solve::<BigInt>(s, 1, Direction::Forward, None, false, false)
+                .unwrap()
+                .to_string(),
+            "-25"
+        );
+        assert_eq!(
+            solve::<BigInt>(s, 1, Direction::Backward, None, false, false)
+                .unwrap()
+                .to_string(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn day9_non_polynomial() {
+        // powers of two never difference down to zeros
+        let s = "0 3 6 9 12 15
+2 4 8 16 32 64 128";
+        let err = solve::<i64>(s, 1, Direction::Forward, None, false, false).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "history on line 2: differences never vanish, still nonzero at degree 6"
+        );
+        assert_eq!(err.downcast_ref::<NotPolynomial>().unwrap().degree, 6);
+
+        let geometric = Some(Fallback::Geometric);
+        assert_eq!(
+            solve::<i64>(s, 1, Direction::Forward, geometric, false, false).unwrap(),
+            18 + 256
+        );
+        assert_eq!(
+            solve::<BigInt>(s, 1, Direction::Backward, geometric, false, false)
+                .unwrap()
+                .to_string(),
+            "-2"
+        );
+        // 1/2 isn't a whole number
+        assert!(solve::<i64>(s, 2, Direction::Backward, geometric, false, false).is_err());
+
+        // fibonacci is no geometric sequence, but is a recurrence
+        let fibonacci = [1i64, 1, 2, 3, 5, 8, 13, 21];
+        let err = extrapolate(&fibonacci, 1, Direction::Forward, geometric).unwrap_err();
+        assert!(err.is::<NotPolynomial>(), "{err}");
+        assert_eq!(
+            format!("{err:#}"),
+            "no geometric model fits either: differences never vanish, still nonzero at degree 7"
+        );
+        let recurrence = Some(Fallback::Recurrence);
+        assert_eq!(
+            extrapolate(&fibonacci, 1, Direction::Forward, recurrence).unwrap(),
+            34
+        );
+        // 0, 1, -1 running back
+        assert_eq!(
+            extrapolate(&fibonacci, 3, Direction::Backward, recurrence).unwrap(),
+            -1
+        );
+        let fitted = Recurrence::fit(&fibonacci, 8).unwrap().unwrap();
+        assert_eq!(fitted.formula(), "x[n] = x[n-1] + x[n-2]");
+
+        // x[n] = 3x[n-1] / 2 - x[n-2]
+        let fitted = Recurrence::fit(&[1i64, 14, 20, 16, 4, -10, -19], 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fitted.formula(), "x[n] = (3/2)x[n-1] - x[n-2]");
+        assert!(fitted.extrapolate::<i64>(1, Direction::Forward).is_err());
+
+        // polynomials still take the newton path, even with a fallback
+        assert_eq!(
+            extrapolate(&[1i64, 3, 6, 10], 1, Direction::Forward, recurrence).unwrap(),
+            15
+        );
+        // one value can't confirm anything
+        assert!(extrapolate(&[5i64], 1, Direction::Forward, None).is_err());
+        assert_eq!(Newton::new(&[5i64]).unwrap().unconfirmed_degree(), Some(0));
+        assert_eq!(Newton::new(&[5i64, 5]).unwrap().unconfirmed_degree(), None);
+    }
+
+    #[test]
+    fn day9_shortest_recurrence() {
+        let s = "1 1 2 3 5 8 13 21
+3 6 12 24 48 96
+1 3 6 10 15 21 28
+0 1 1 2 4 7 13 24 44
+1 14 20 16 4 -10 -19";
+        let histories: Vec<Vec<i128>> = parse::<i64>(s)
+            .unwrap()
+            .iter()
+            .map(|history| history.iter().map(|x| *x as i128).collect())
+            .collect();
+        let shortest = |history: &[i128]| {
+            Recurrence::shortest(history)
+                .unwrap()
+                .iter()
+                .map(|coefficient| coefficient.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(shortest(&histories[0]), ["1", "1"]);
+        assert_eq!(shortest(&histories[1]), ["2"]);
+        // a polynomial of degree d is a recurrence of order d + 1
+        assert_eq!(shortest(&histories[2]), ["3", "-3", "1"]);
+        // tribonacci
+        assert_eq!(shortest(&histories[3]), ["1", "1", "1"]);
+        assert_eq!(shortest(&histories[4]), ["3/2", "-1"]);
+        assert!(shortest(&[0, 0, 0]).is_empty());
+        // x[n] = 5x[n-4], the zeros in between don't say anything shorter
+        assert_eq!(shortest(&[1, 0, 0, 0, 5]), ["0", "0", "0", "5"]);
+
+        let recurrence = |line: usize| Recurrence::fit(&parse::<i64>(s).unwrap()[line], 9);
+        let tribonacci = recurrence(3).unwrap().unwrap();
+        assert_eq!(tribonacci.formula(), "x[n] = x[n-1] + x[n-2] + x[n-3]");
+        assert_eq!(
+            tribonacci
+                .extrapolate::<i64>(1, Direction::Forward)
+                .unwrap(),
+            81
+        );
+        assert_eq!(
+            tribonacci
+                .extrapolate::<i64>(2, Direction::Forward)
+                .unwrap(),
+            149
+        );
+        // 1 + 0 + x[-1] = 1
+        assert_eq!(
+            tribonacci
+                .extrapolate::<i64>(1, Direction::Backward)
+                .unwrap(),
+            0
+        );
+        // the matrix powers agree with running the recurrence step by step
+        let mut running = parse::<i64>(s).unwrap()[3].clone();
+        for steps in 1..=30 {
+            let n = running.len();
+            running.push(running[n - 1] + running[n - 2] + running[n - 3]);
+            assert_eq!(
+                tribonacci
+                    .extrapolate::<i64>(steps, Direction::Forward)
+                    .unwrap(),
+                running[n],
+                "{steps} steps"
+            );
+        }
+        // and far away steps don't take any longer
+        let alternating = Recurrence::fit(&[1i64, -1, 1, -1, 1, -1], 1)
+            .unwrap()
+            .unwrap();
+        let far = 1_000_000_000_000;
+        assert_eq!(
+            alternating
+                .extrapolate::<i64>(far, Direction::Forward)
+                .unwrap(),
+            -1
+        );
+        assert_eq!(
+            alternating
+                .extrapolate::<i64>(far + 1, Direction::Backward)
+                .unwrap(),
+            -1
+        );
+        assert_eq!(
+            alternating
+                .extrapolate::<i64>(i64::MAX, Direction::Forward)
+                .unwrap(),
+            1
+        );
+        let err = tribonacci
+            .extrapolate::<i64>(far, Direction::Forward)
+            .unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+
+        let triangular = recurrence(2).unwrap().unwrap();
+        assert_eq!(
+            triangular
+                .extrapolate::<i64>(1, Direction::Forward)
+                .unwrap(),
+            36
+        );
+        assert_eq!(
+            triangular
+                .extrapolate::<i64>(2, Direction::Backward)
+                .unwrap(),
+            0
+        );
+        // not enough history left over to confirm an order 3 fit
+        assert!(Recurrence::fit(&[1i64, 3, 6, 10, 15, 21], 9)
+            .unwrap()
+            .is_none());
+        assert!(recurrence(0)
+            .unwrap()
+            .unwrap()
+            .extrapolate::<i64>(0, Direction::Forward)
+            .is_err());
+    }
+
+    #[test]
+    fn day9_pyramid() {
+        let history = [10i64, 13, 16, 21, 30, 45];
+        let predict = |steps, direction| extrapolate(&history, steps, direction, None).unwrap();
+        let forward = pyramid(
+            &history,
+            &[predict(1, Direction::Forward)],
+            Direction::Forward,
+        );
+        assert_eq!(
+            forward.unwrap(),
+            "    10    13    16    21    30    45  [68]
+        3     3     5     9    15  [23]
+           0     2     4     6   [8]
+              2     2     2   [2]
+                 0     0   [0]"
+        );
+
+        let predictions = [
+            predict(1, Direction::Backward),
+            predict(2, Direction::Backward),
+        ];
+        let backward = pyramid(&history, &predictions, Direction::Backward);
+        assert_eq!(
+            backward.unwrap(),
+            "  [-4]   [5]    10    13    16    21    30    45
+      [9]   [5]     3     3     5     9    15
+        [-4]  [-2]     0     2     4     6
+            [2]   [2]     2     2     2
+               [0]   [0]     0     0"
+        );
+
+        // nothing to predict with, the pyramid just runs out
+        assert_eq!(
+            pyramid(&[1i64, 2, 4], &[], Direction::Forward).unwrap(),
+            " 1 2 4\n  1 2\n   1"
+        );
     }
 }
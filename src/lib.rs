@@ -1,22 +1,34 @@
 use std::{
+    fmt::{Debug, Display},
     fs::{File, OpenOptions},
     io::{BufRead, BufReader},
+    num::{IntErrorKind, ParseIntError},
+    ops::{Add, Div, Mul, Rem, Sub},
+    str::FromStr,
 };
 
-use clap::{arg, Command};
+use clap::{arg, Arg, ArgMatches, Command};
 
 use anyhow::{anyhow, Result};
 use regex::{Captures, Regex};
 
-fn cli() -> Command {
-    Command::new("aoc").args([
-        arg!(-p --part <part> "part to solve"),
-        arg!(-i --input <input> "aoc problem file"),
-    ])
+fn cli(extra_args: Vec<Arg>) -> Command {
+    Command::new("aoc")
+        .args([
+            arg!(-p --part <part> "part to solve"),
+            arg!(-i --input <input> "aoc problem file"),
+        ])
+        .args(extra_args)
 }
 
 pub fn fetch_problem() -> Result<Problem> {
-    let cmd = cli();
+    let (problem, _) = fetch_problem_with_args(vec![])?;
+    Ok(problem)
+}
+
+// same as fetch_problem, but lets a day add its own flags on top of -p and -i
+pub fn fetch_problem_with_args(extra_args: Vec<Arg>) -> Result<(Problem, ArgMatches)> {
+    let cmd = cli(extra_args);
     let matches = cmd.get_matches();
     let part = matches
         .get_one::<String>("part")
@@ -31,7 +43,33 @@ pub fn fetch_problem() -> Result<Problem> {
         _ => panic!("please specify p1 or p2"),
     };
 
-    Ok(Problem::new(part, path.to_owned()))
+    Ok((Problem::new(part, path.to_owned()), matches))
+}
+
+// left aligned columns two spaces apart, as wide as their widest cell,
+// for the reports days print alongside their answer
+pub fn render_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> String {
+    let header = header.map(|title| title.to_string());
+    let mut widths = header.each_ref().map(|title| title.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
 }
 
 pub fn open_into_buffered_reader(path: &str) -> Result<BufReader<File>> {
@@ -57,26 +95,25 @@ impl Pattern {
     }
 }
 
-pub fn search(line: &str, patterns: Vec<Pattern>) -> Result<Captures> {
+pub fn search(line: &str, patterns: Vec<Pattern>) -> Result<Captures<'_>> {
     let mut expect = String::new();
     for pattern in patterns {
         expect.push_str(pattern.into_value());
     }
 
     let re = Regex::new(&expect)?;
-    Ok(re
-        .captures(line)
-        .ok_or(anyhow!("failed to capture on pattern {expect}"))?)
+    re.captures(line)
+        .ok_or(anyhow!("failed to capture on pattern {expect}"))
 }
 
 pub fn get_line(reader: &mut BufReader<File>) -> Option<String> {
     let mut line = String::new();
     let bytes_read = reader.read_line(&mut line);
-    return match bytes_read {
+    match bytes_read {
         Ok(0) => None,
         Ok(_) => Some(line),
         _ => None,
-    };
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -96,20 +133,616 @@ impl Problem {
     }
 }
 
-pub fn lcm(a: usize, b: usize) -> usize {
-    a * b / gcd(a, b)
+pub fn lcm<T: Unsigned>(a: T, b: T) -> Result<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Ok(T::ZERO);
+    }
+
+    (a / gcd(a, b))
+        .checked_mul(b)
+        .ok_or_else(|| overflow::<T>(format!("lcm of {a} and {b}")))
 }
 
-pub fn gcd(a: usize, b: usize) -> usize {
+pub fn gcd<T: Unsigned>(a: T, b: T) -> T {
     let mut max = a.max(b);
     let mut min = a.min(b);
 
-    loop {
+    while min != T::ZERO {
         let res = max % min;
-        if res == 0 {
-            return min;
-        }
         max = min;
         min = res;
     }
+
+    max
+}
+
+// ====================================================
+//                      Numbers
+// ====================================================
+
+// a result too big for the integer width it was computed in, callers
+// can check for it with `anyhow::Error::is` and retry with a wider type
+#[derive(Debug)]
+pub struct Overflow {
+    pub what: String,
+    pub width: &'static str,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} overflows {}", self.what, self.width)
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+// the name an integer type goes by in overflow messages
+pub trait Width {
+    const NAME: &'static str;
+}
+
+pub fn overflow<T: Width>(what: String) -> anyhow::Error {
+    anyhow::Error::new(Overflow {
+        what,
+        width: T::NAME,
+    })
+}
+
+// unsigned integer widths the numeric puzzles can be solved in, the
+// checked operations are what let a solver report overflow instead of wrapping
+pub trait Unsigned:
+    Width
+    + Bisect
+    + Debug
+    + Display
+    + From<u8>
+    + FromStr<Err = ParseIntError>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn isqrt(self) -> Self;
+}
+
+macro_rules! impl_unsigned {
+    ($($int:ty),*) => {$(
+        impl Width for $int {
+            const NAME: &'static str = stringify!($int);
+        }
+
+        impl Unsigned for $int {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$int>::checked_add(self, rhs)
+            }
+
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$int>::checked_sub(self, rhs)
+            }
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$int>::checked_mul(self, rhs)
+            }
+
+            fn isqrt(self) -> Self {
+                <$int>::isqrt(self)
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128, usize);
+
+// parses a number, turning a value too big for T into an Overflow
+pub fn parse_unsigned<T: Unsigned>(number: &str) -> Result<T> {
+    number.parse::<T>().map_err(|err| match err.kind() {
+        IntErrorKind::PosOverflow => overflow::<T>(format!("number {number}")),
+        _ => anyhow!("failed to parse {number:?} as a number: {err}"),
+    })
+}
+
+// signed integer types the sequence puzzles can be solved in, fixed width
+// or not. Takes references so big integers don't have to be copied around
+pub trait Signed: Width + Clone + Debug + Display + PartialEq {
+    fn zero() -> Self;
+    fn from_i64(value: i64) -> Self;
+    fn from_i128(value: i128) -> Option<Self>;
+    fn to_i128(&self) -> Option<i128>;
+    fn parse(number: &str) -> Result<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    // None when the divisor doesn't go in evenly, as well as on overflow
+    fn checked_div_exact(&self, divisor: i64) -> Option<Self>;
+}
+
+macro_rules! impl_signed {
+    ($($int:ty),*) => {$(
+        impl Width for $int {
+            const NAME: &'static str = stringify!($int);
+        }
+
+        impl Signed for $int {
+            fn zero() -> Self {
+                0
+            }
+
+            fn from_i64(value: i64) -> Self {
+                value.into()
+            }
+
+            fn from_i128(value: i128) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn to_i128(&self) -> Option<i128> {
+                Some((*self).into())
+            }
+
+            fn parse(number: &str) -> Result<Self> {
+                number.parse::<$int>().map_err(|err| match err.kind() {
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                        overflow::<$int>(format!("number {number}"))
+                    }
+                    _ => anyhow!("failed to parse {number:?} as a number: {err}"),
+                })
+            }
+
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$int>::checked_add(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$int>::checked_sub(*self, *rhs)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                <$int>::checked_mul(*self, *rhs)
+            }
+
+            fn checked_div_exact(&self, divisor: i64) -> Option<Self> {
+                let divisor = divisor.into();
+                if <$int>::checked_rem(*self, divisor)? != 0 {
+                    return None;
+                }
+                <$int>::checked_div(*self, divisor)
+            }
+        }
+    )*};
+}
+
+impl_signed!(i64, i128);
+
+// ====================================================
+//                    Big Integers
+// ====================================================
+
+// integers that grow instead of overflowing, for sequences that outgrow i128
+pub use num_bigint::BigInt;
+
+impl Width for BigInt {
+    const NAME: &'static str = "big integers";
+}
+
+impl Signed for BigInt {
+    fn zero() -> Self {
+        BigInt::default()
+    }
+
+    fn from_i64(value: i64) -> Self {
+        value.into()
+    }
+
+    fn from_i128(value: i128) -> Option<Self> {
+        Some(value.into())
+    }
+
+    fn to_i128(&self) -> Option<i128> {
+        self.try_into().ok()
+    }
+
+    fn parse(number: &str) -> Result<Self> {
+        // num-bigint also takes underscores between digits, the puzzles don't
+        let digits = number.strip_prefix(['-', '+']).unwrap_or(number);
+        if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+            return Err(anyhow!("failed to parse {number:?} as a number"));
+        }
+        number
+            .parse::<BigInt>()
+            .map_err(|err| anyhow!("failed to parse {number:?} as a number: {err}"))
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn checked_div_exact(&self, divisor: i64) -> Option<Self> {
+        if divisor == 0 || !(self % divisor).is_zero() {
+            return None;
+        }
+        Some(self / divisor)
+    }
+}
+
+// ====================================================
+//                     Rationals
+// ====================================================
+
+// an exact fraction, always in lowest terms with a positive denominator
+// so equal values compare equal. Every operation is checked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+impl Rational {
+    pub const ZERO: Self = Self { numer: 0, denom: 1 };
+    pub const ONE: Self = Self { numer: 1, denom: 1 };
+
+    // None for a zero denominator, or one that can't be made positive
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = i128::try_from(gcd(numer.unsigned_abs(), denom.unsigned_abs())).ok()?;
+        let (numer, denom) = (numer / divisor, denom / divisor);
+        if denom < 0 {
+            Some(Self {
+                numer: numer.checked_neg()?,
+                denom: denom.checked_neg()?,
+            })
+        } else {
+            Some(Self { numer, denom })
+        }
+    }
+
+    pub fn integer(value: i128) -> Self {
+        Self {
+            numer: value,
+            denom: 1,
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    // None unless the fraction is a whole number
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.denom == 1).then_some(self.numer)
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let divisor = gcd(self.denom as u128, rhs.denom as u128) as i128;
+        let numer = self
+            .numer
+            .checked_mul(rhs.denom / divisor)?
+            .checked_add(rhs.numer.checked_mul(self.denom / divisor)?)?;
+        Self::new(numer, self.denom.checked_mul(rhs.denom / divisor)?)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(Self {
+            numer: rhs.numer.checked_neg()?,
+            denom: rhs.denom,
+        })
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        // cancel across first so the products stay as small as they can
+        let a = Self::new(self.numer, rhs.denom)?;
+        let b = Self::new(rhs.numer, self.denom)?;
+        Self::new(a.numer.checked_mul(b.numer)?, a.denom.checked_mul(b.denom)?)
+    }
+
+    // None when dividing by zero as well as on overflow
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(Self::new(rhs.denom, rhs.numer)?)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+// ====================================================
+//                   Binary Search
+// ====================================================
+
+// integers a search can bisect, the midpoint is rounded down and
+// never overflows, even across the whole range of the type
+pub trait Bisect: Copy + Ord {
+    fn midpoint_floor(low: Self, high: Self) -> Self;
+    fn successor(self) -> Self;
+    fn predecessor(self) -> Self;
+}
+
+macro_rules! impl_bisect {
+    ($($int:ty),*) => {$(
+        impl Bisect for $int {
+            fn midpoint_floor(low: Self, high: Self) -> Self {
+                // shared bits plus half the differing bits, the shift is arithmetic
+                // for signed types so this floors instead of overflowing
+                (low & high) + ((low ^ high) >> 1)
+            }
+
+            fn successor(self) -> Self {
+                self + 1
+            }
+
+            fn predecessor(self) -> Self {
+                self - 1
+            }
+        }
+    )*};
+}
+
+impl_bisect!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// first value in [low, high] where `is_true` holds, for a predicate that
+// is false up to some point and true from there on
+pub fn first_true<T: Bisect>(low: T, high: T, is_true: impl Fn(T) -> bool) -> Option<T> {
+    if low > high || !is_true(high) {
+        return None;
+    }
+
+    // is_true(high) always holds, so each step shrinks the range
+    let (mut low, mut high) = (low, high);
+    while low < high {
+        let mid = T::midpoint_floor(low, high);
+        if is_true(mid) {
+            high = mid;
+        } else {
+            low = mid.successor();
+        }
+    }
+
+    Some(high)
+}
+
+// last value in [low, high] where `is_true` holds, for a predicate that
+// is true up to some point and false from there on
+pub fn last_true<T: Bisect>(low: T, high: T, is_true: impl Fn(T) -> bool) -> Option<T> {
+    if low > high || !is_true(low) {
+        return None;
+    }
+
+    // is_true(low) always holds, rounding the midpoint up keeps it moving
+    let (mut low, mut high) = (low, high);
+    while low < high {
+        let mid = T::midpoint_floor(low, high.predecessor()).successor();
+        if is_true(mid) {
+            low = mid;
+        } else {
+            high = mid.predecessor();
+        }
+    }
+
+    Some(low)
+}
+
+// where `f` is largest in [low, high], for an `f` that strictly rises and then
+// never rises again (a flat top is fine, the first of the tied values is returned)
+pub fn peak<T: Bisect, V: Ord>(low: T, high: T, f: impl Fn(T) -> V) -> Option<T> {
+    if low > high {
+        return None;
+    }
+    if low == high {
+        return Some(low);
+    }
+
+    Some(first_true(low, high.predecessor(), |x| f(x) >= f(x.successor())).unwrap_or(high))
+}
+
+// the range in [low, high] where `is_true` holds, for a predicate that is
+// false, then true, then false again and holds at `peak` if it holds anywhere
+pub fn true_range<T: Bisect>(
+    low: T,
+    high: T,
+    peak: T,
+    is_true: impl Fn(T) -> bool,
+) -> Option<(T, T)> {
+    if peak < low || peak > high || !is_true(peak) {
+        return None;
+    }
+
+    Some((
+        first_true(low, peak, &is_true)?,
+        last_true(peak, high, &is_true)?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        first_true, gcd, last_true, lcm, parse_unsigned, peak, render_table, true_range, BigInt,
+        Bisect, Overflow, Rational, Signed,
+    };
+
+    #[test]
+    fn lib_big_integers() {
+        let big = |number: &str| BigInt::parse(number).unwrap();
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("+42").to_string(), "42");
+        assert_eq!(
+            big("-123456789012345678901234567890").to_string(),
+            "-123456789012345678901234567890"
+        );
+        assert!(BigInt::parse("12a").is_err());
+        assert!(BigInt::parse("-").is_err());
+
+        let a = big("340282366920938463463374607431768211455"); // u128::MAX
+        let b = big("-1000000000000000000000");
+        assert_eq!(
+            a.checked_mul(&a).unwrap().to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!(
+            a.checked_add(&b).unwrap().to_string(),
+            "340282366920938462463374607431768211455"
+        );
+        assert_eq!(
+            b.checked_sub(&a).unwrap().to_string(),
+            "-340282366920938464463374607431768211455"
+        );
+        assert_eq!(b.checked_sub(&b).unwrap(), BigInt::zero());
+        assert!(b.checked_sub(&b).unwrap().is_zero());
+        assert_eq!(
+            b.checked_div_exact(-8).unwrap().to_string(),
+            "125000000000000000000"
+        );
+        assert_eq!(b.checked_div_exact(7), None);
+        assert_eq!(b.checked_div_exact(0), None);
+        assert_eq!(Signed::checked_div_exact(&-12i64, 4), Some(-3));
+        assert_eq!(Signed::checked_div_exact(&13i64, 4), None);
+        assert_eq!(Signed::checked_div_exact(&i128::MIN, -1), None);
+        assert!(BigInt::parse("1_000").is_err());
+
+        assert_eq!(BigInt::from_i64(i64::MIN).to_string(), i64::MIN.to_string());
+        assert_eq!(BigInt::from_i64(-5).to_i128(), Some(-5));
+        assert_eq!(big(&i128::MIN.to_string()).to_i128(), Some(i128::MIN));
+        assert_eq!(a.to_i128(), None);
+        assert_eq!(
+            BigInt::from_i128(i128::MIN).unwrap().to_string(),
+            i128::MIN.to_string()
+        );
+        assert_eq!(BigInt::from_i128(0), Some(BigInt::zero()));
+        assert_eq!(<i64 as Signed>::from_i128(i64::MAX as i128 + 1), None);
+    }
+
+    #[test]
+    fn lib_checked_numbers() {
+        assert_eq!(gcd(12u32, 18), 6);
+        assert_eq!(gcd(0u32, 7), 7);
+        assert_eq!(lcm(4usize, 6).unwrap(), 12);
+        assert_eq!(lcm(0usize, 6).unwrap(), 0);
+
+        let err = lcm(u64::MAX, u64::MAX - 1).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert_eq!(
+            lcm(u64::MAX as u128, u64::MAX as u128 - 1).unwrap(),
+            u64::MAX as u128 * (u64::MAX as u128 - 1)
+        );
+
+        assert_eq!(
+            parse_unsigned::<u64>("18446744073709551615").unwrap(),
+            u64::MAX
+        );
+        let err = parse_unsigned::<u64>("18446744073709551616").unwrap_err();
+        assert_eq!(err.to_string(), "number 18446744073709551616 overflows u64");
+        assert!(!parse_unsigned::<u64>("12a").unwrap_err().is::<Overflow>());
+    }
+
+    #[test]
+    fn lib_rationals() {
+        let fraction = |numer, denom| Rational::new(numer, denom).unwrap();
+        assert_eq!(fraction(6, -4), fraction(-3, 2));
+        assert_eq!(fraction(6, -4).to_string(), "-3/2");
+        assert_eq!(fraction(0, -5), Rational::ZERO);
+        assert_eq!(Rational::new(1, 0), None);
+        assert_eq!(Rational::new(1, i128::MIN), None);
+
+        let half = fraction(1, 2);
+        let third = fraction(1, 3);
+        assert_eq!(half.checked_add(third), Some(fraction(5, 6)));
+        assert_eq!(half.checked_sub(third), Some(fraction(1, 6)));
+        assert_eq!(half.checked_mul(third), Some(fraction(1, 6)));
+        assert_eq!(half.checked_div(third), Some(fraction(3, 2)));
+        assert_eq!(half.checked_div(Rational::ZERO), None);
+        assert_eq!(fraction(4, 2).to_integer(), Some(2));
+        assert_eq!(half.to_integer(), None);
+
+        let big = Rational::integer(i128::MAX);
+        assert_eq!(big.checked_add(Rational::ONE), None);
+        assert_eq!(big.checked_mul(fraction(1, i128::MAX)), Some(Rational::ONE));
+    }
+
+    #[test]
+    fn lib_midpoint_does_not_overflow() {
+        assert_eq!(u8::midpoint_floor(254, 255), 254);
+        assert_eq!(
+            u128::midpoint_floor(u128::MAX - 2, u128::MAX),
+            u128::MAX - 1
+        );
+        assert_eq!(i64::midpoint_floor(i64::MIN, i64::MAX), -1);
+        assert_eq!(i32::midpoint_floor(-3, -2), -3);
+    }
+
+    #[test]
+    fn lib_monotone_search() {
+        assert_eq!(first_true(0u32, 100, |x| x * x >= 50), Some(8));
+        assert_eq!(first_true(0u32, 100, |x| x > 100), None);
+        assert_eq!(first_true(5u32, 4, |_| true), None);
+        assert_eq!(last_true(0u32, 100, |x| x * x <= 50), Some(7));
+        assert_eq!(last_true(0u32, 100, |_| false), None);
+        assert_eq!(first_true(i8::MIN, i8::MAX, |x| x >= -100), Some(-100));
+        assert_eq!(last_true(u64::MIN, u64::MAX, |_| true), Some(u64::MAX));
+        assert_eq!(first_true(u64::MIN, u64::MAX, |_| true), Some(0));
+    }
+
+    #[test]
+    fn lib_unimodal_search() {
+        let bell = |x: i64| x * (10 - x);
+        assert_eq!(peak(0, 10, bell), Some(5));
+        assert_eq!(peak(0, 9, |x: i64| x * (9 - x)), Some(4));
+        assert_eq!(peak(3, 3, bell), Some(3));
+        assert_eq!(peak(0, 4, bell), Some(4));
+        assert_eq!(true_range(0, 10, 5, |x| bell(x) > 20), Some((3, 7)));
+        assert_eq!(true_range(0, 10, 5, |x| bell(x) > 25), None);
+    }
+
+    #[test]
+    fn lib_render_table() {
+        let rows = [
+            ["1".to_string(), "zwölf".to_string(), String::new()],
+            ["10".to_string(), "-".to_string(), "no digits".to_string()],
+        ];
+        assert_eq!(
+            render_table(["line", "word", "note"], &rows),
+            "line  word   note
+1     zwölf
+10    -      no digits
+"
+        );
+        assert_eq!(render_table(["a", "b"], &[]), "a  b\n");
+    }
 }